@@ -1,37 +1,109 @@
+use std::env;
 use std::io::{self, Read};
 
+mod rules;
+
+use rules::{Outcome, Rules};
+
 type Error = Box<dyn std::error::Error + Send + Sync>;
 type Result<T, E = Error> = std::result::Result<T, E>;
 
+/// Plays by `--rules <rps|rpsls|n>`, the latter for `n` shapes in a circle.
 fn main() -> Result<()> {
     let mut input = String::new();
     io::stdin().read_to_string(&mut input)?;
 
-    let r = solve(&input);
-    println!("p1 = {}", r.0);
-    println!("p2 = {}", r.1);
+    let mut rules = Rules::classic();
+    let mut args = env::args().skip(1);
+    while let Some(flag) = args.next() {
+        let mut value = || args.next().ok_or(format!("missing value for `{flag}`"));
+        match flag.as_str() {
+            "--rules" => {
+                rules = match value()?.as_str() {
+                    "rps" => Rules::classic(),
+                    "rpsls" => Rules::rpsls(),
+                    n => Rules::cyclic((1..=n.parse()?).map(|i| format!("shape {i}")).collect())?,
+                }
+            }
+            _ => return Err(format!("unknown flag `{flag}`").into()),
+        }
+    }
+
+    let rounds = parse(&rules, &input)?;
+    // Either reading of the guide may not make sense with the rules.
+    match part1(&rules, &rounds) {
+        Ok(score) => println!("p1 = {score}"),
+        Err(e) => println!("p1 ({e})"),
+    }
+    match part2(&rules, &rounds) {
+        Ok(score) => println!("p2 = {score}"),
+        Err(e) => println!("p2 ({e})"),
+    }
 
     Ok(())
 }
 
-fn solve(input: &str) -> (i32, i32) {
+/// Round of the guide, with the letters in both columns counted from `A` in
+/// the first one and from `X` in the second, wrapping around after `Z`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Round {
+    line: usize,
+    opponent: usize,
+    column: usize,
+}
+
+fn parse(rules: &Rules, input: &str) -> Result<Vec<Round>> {
     input
         .lines()
-        .filter_map(|l| l.split_once(' '))
-        .try_fold((0, 0), |(mut s1, mut s2), (l, r)| {
-            let l = (l.as_bytes().first()? - b'A') as i32 + 1;
-            let r = (r.as_bytes().first()? - b'X') as i32 + 1;
-
-            s1 += score(l, r);
-            s2 += score(l, 1 + (l + r) % 3);
-
-            Some((s1, s2))
+        .enumerate()
+        .filter(|(_, l)| !l.is_empty())
+        .map(|(i, l)| {
+            let err = |msg: &str| Error::from(format!("line {}: {msg} in `{l}`", i + 1));
+            let (opponent, column) =
+                match l.split_once(' ').map(|(l, r)| (l.as_bytes(), r.as_bytes())) {
+                    Some(([l], [r])) if l.is_ascii_uppercase() && r.is_ascii_uppercase() => {
+                        ((l - b'A') as usize, (r + 26 - b'X') as usize % 26)
+                    }
+                    _ => return Err(err("invalid round")),
+                };
+            if opponent >= rules.len() {
+                return Err(err(&format!("only {} shapes", rules.len())));
+            }
+            Ok(Round {
+                line: i + 1,
+                opponent,
+                column,
+            })
         })
-        .expect("No moves parsed.")
+        .collect()
+}
+
+/// Total score with the second column being the shape to play.
+fn part1(rules: &Rules, rounds: &[Round]) -> Result<u32> {
+    rounds.iter().try_fold(0, |score, r| {
+        if r.column >= rules.len() {
+            return Err(format!("line {}: only {} shapes", r.line, rules.len()).into());
+        }
+        Ok(score + rules.score(r.column, r.opponent))
+    })
 }
 
-fn score(l: i32, r: i32) -> i32 {
-    (4 + r - l) % 3 * 3 + r
+/// Total score with the second column being the outcome to get, `X` for a
+/// loss, `Y` for a draw and `Z` for a win.
+fn part2(rules: &Rules, rounds: &[Round]) -> Result<u32> {
+    rounds.iter().try_fold(0, |score, r| {
+        let outcome = *Outcome::ALL
+            .get(r.column)
+            .ok_or_else(|| format!("line {}: invalid outcome", r.line))?;
+        let shape = rules.respond(r.opponent, outcome).ok_or_else(|| {
+            format!(
+                "line {}: can't get a {outcome:?} against {}",
+                r.line,
+                rules.name(r.opponent)
+            )
+        })?;
+        Ok(score + rules.score(shape, r.opponent))
+    })
 }
 
 #[cfg(test)]
@@ -44,11 +116,26 @@ C Z"#;
 
     #[test]
     fn part1_example() {
-        assert_eq!(solve(INPUT).0, 15);
+        let rules = Rules::classic();
+        assert_eq!(part1(&rules, &parse(&rules, INPUT).unwrap()).unwrap(), 15);
     }
 
     #[test]
     fn part2_example() {
-        assert_eq!(solve(INPUT).1, 12);
+        let rules = Rules::classic();
+        assert_eq!(part2(&rules, &parse(&rules, INPUT).unwrap()).unwrap(), 12);
+    }
+
+    #[test]
+    fn readings_are_independent() {
+        let rules = Rules::rpsls();
+        let rounds = parse(&rules, "A X\nE B").unwrap();
+        // `B` wraps around to the fifth shape, lizard against lizard.
+        assert_eq!(part1(&rules, &rounds).unwrap(), 4 + 8);
+        let err = part2(&rules, &rounds).unwrap_err();
+        assert_eq!(err.to_string(), "line 2: invalid outcome");
+
+        assert!(parse(&rules, "F X").is_err());
+        assert!(part1(&Rules::classic(), &parse(&rules, "A B").unwrap()).is_err());
     }
 }
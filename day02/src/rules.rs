@@ -0,0 +1,183 @@
+use crate::Result;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Loss,
+    Draw,
+    Win,
+}
+
+impl Outcome {
+    pub const ALL: [Outcome; 3] = [Outcome::Loss, Outcome::Draw, Outcome::Win];
+}
+
+/// Shapes of a game, what beats what and how rounds are scored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rules {
+    names: Vec<String>,
+    /// `beats[a][b]` if shape `a` beats shape `b`, neither beating the other
+    /// being a draw.
+    beats: Vec<Vec<bool>>,
+    shape_scores: Vec<u32>,
+    /// Scores of a loss, a draw and a win.
+    outcome_scores: [u32; 3],
+}
+
+impl Rules {
+    /// Checks that no shape beats itself, or a shape that beats it back.
+    pub fn new(
+        names: Vec<String>,
+        beats: &[(usize, usize)],
+        shape_scores: Vec<u32>,
+        outcome_scores: [u32; 3],
+    ) -> Result<Self> {
+        let n = names.len();
+        if shape_scores.len() != n {
+            return Err(format!("{} scores for {n} shapes", shape_scores.len()).into());
+        }
+        let mut table = vec![vec![false; n]; n];
+        for &(a, b) in beats {
+            if a >= n || b >= n {
+                return Err(format!("no shape {} in {n}", a.max(b)).into());
+            }
+            if a == b || table[b][a] {
+                return Err(format!("`{}` and `{}` beat each other", names[a], names[b]).into());
+            }
+            table[a][b] = true;
+        }
+        Ok(Self {
+            names,
+            beats: table,
+            shape_scores,
+            outcome_scores,
+        })
+    }
+
+    /// Odd number of shapes in a circle, each beating the shapes an odd
+    /// number of steps behind it, scoring 1 to `n` plus 0, 3 or 6 for a
+    /// loss, a draw or a win. Every shape beats and loses to half the others.
+    pub fn cyclic(names: Vec<String>) -> Result<Self> {
+        let n = names.len();
+        if n.is_multiple_of(2) {
+            return Err(format!("can't balance {n} shapes, need an odd number").into());
+        }
+        let beats = (0..n)
+            .flat_map(|a| (0..n).map(move |b| (a, b)))
+            .filter(|&(a, b)| (n + a - b) % n % 2 == 1)
+            .collect::<Vec<_>>();
+        Self::new(names, &beats, (1..=n as u32).collect(), [0, 3, 6])
+    }
+
+    /// Rock, paper and scissors.
+    pub fn classic() -> Self {
+        Self::cyclic(names(&["Rock", "Paper", "Scissors"])).unwrap()
+    }
+
+    /// Rock, paper, scissors, Spock and lizard, scoring 1 to 5.
+    pub fn rpsls() -> Self {
+        Self::cyclic(names(&["Rock", "Paper", "Scissors", "Spock", "Lizard"])).unwrap()
+    }
+
+    /// Number of shapes.
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn name(&self, shape: usize) -> &str {
+        &self.names[shape]
+    }
+
+    /// Outcome of playing `shape` against `other`.
+    pub fn outcome(&self, shape: usize, other: usize) -> Outcome {
+        match (self.beats[shape][other], self.beats[other][shape]) {
+            (true, _) => Outcome::Win,
+            (_, true) => Outcome::Loss,
+            _ => Outcome::Draw,
+        }
+    }
+
+    /// Score of a round playing `shape` against `other`.
+    pub fn score(&self, shape: usize, other: usize) -> u32 {
+        self.shape_scores[shape] + self.outcome_scores[self.outcome(shape, other) as usize]
+    }
+
+    /// Best scoring shape to get `outcome` against `other`, if any.
+    pub fn respond(&self, other: usize, outcome: Outcome) -> Option<usize> {
+        (0..self.len())
+            .filter(|&s| self.outcome(s, other) == outcome)
+            .max_by_key(|&s| self.shape_scores[s])
+    }
+}
+
+fn names(names: &[&str]) -> Vec<String> {
+    names.iter().map(|s| s.to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classic() {
+        let rules = Rules::classic();
+        // The arithmetic the puzzle's scoring boils down to.
+        for l in 0..3 {
+            for r in 0..3 {
+                assert_eq!(rules.score(r, l), ((4 + r - l) % 3 * 3 + r + 1) as u32);
+            }
+        }
+        assert_eq!(rules.outcome(0, 2), Outcome::Win);
+        assert_eq!(rules.respond(0, Outcome::Loss), Some(2));
+    }
+
+    #[test]
+    fn rpsls() {
+        let rules = Rules::rpsls();
+        let shape = |name| (0..rules.len()).find(|&s| rules.name(s) == name).unwrap();
+        for (a, b) in [
+            ("Scissors", "Paper"),
+            ("Paper", "Rock"),
+            ("Rock", "Lizard"),
+            ("Lizard", "Spock"),
+            ("Spock", "Scissors"),
+            ("Scissors", "Lizard"),
+            ("Lizard", "Paper"),
+            ("Paper", "Spock"),
+            ("Spock", "Rock"),
+            ("Rock", "Scissors"),
+        ] {
+            assert_eq!(rules.outcome(shape(a), shape(b)), Outcome::Win, "{a} {b}");
+            assert_eq!(rules.outcome(shape(b), shape(a)), Outcome::Loss, "{b} {a}");
+        }
+        // Spock and paper both beat rock, Spock scoring more.
+        assert_eq!(
+            rules.respond(shape("Rock"), Outcome::Win),
+            Some(shape("Spock"))
+        );
+    }
+
+    #[test]
+    fn cyclic() {
+        let rules = Rules::cyclic(names(&["a", "b", "c", "d", "e", "f", "g"])).unwrap();
+        for s in 0..7 {
+            let count = |o| (0..7).filter(|&t| rules.outcome(s, t) == o).count();
+            assert_eq!(Outcome::ALL.map(count), [3, 1, 3]);
+        }
+        assert!(Rules::cyclic(names(&["a", "b"])).is_err());
+    }
+
+    #[test]
+    fn custom() {
+        let rules =
+            Rules::new(names(&["a", "b", "c"]), &[(0, 1)], vec![5, 0, 1], [1, 2, 4]).unwrap();
+        assert_eq!(rules.score(0, 1), 9);
+        assert_eq!(rules.score(1, 0), 1);
+        assert_eq!(rules.score(2, 0), 3);
+        assert_eq!(rules.respond(2, Outcome::Win), None);
+
+        assert!(Rules::new(names(&["a", "b"]), &[(0, 1), (1, 0)], vec![1, 2], [0, 3, 6]).is_err());
+        assert!(Rules::new(names(&["a"]), &[(0, 0)], vec![1], [0, 3, 6]).is_err());
+        assert!(Rules::new(names(&["a"]), &[(0, 1)], vec![1], [0, 3, 6]).is_err());
+        assert!(Rules::new(names(&["a"]), &[], vec![], [0, 3, 6]).is_err());
+    }
+}
@@ -1,7 +1,11 @@
-#![feature(iter_array_chunks)]
-
+use std::env;
 use std::io::{self, Read};
 
+mod plan;
+mod rucksack;
+
+use rucksack::{Analyzer, Items, Rucksack};
+
 type Error = Box<dyn std::error::Error + Send + Sync>;
 type Result<T, E = Error> = std::result::Result<T, E>;
 
@@ -9,45 +13,91 @@ fn main() -> Result<()> {
     let mut input = String::new();
     io::stdin().read_to_string(&mut input)?;
 
-    println!("p1 = {}", part1(&input));
-    println!("p2 = {}", part2(&input));
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    if !args.is_empty() {
+        return analyze(&input, &args);
+    }
+
+    let analyzer = Analyzer::default();
+    let sacks = analyzer.parse(&input)?;
+    println!("p1 = {}", part1(&analyzer, &sacks));
+    println!("p2 = {}", part2(&analyzer, &sacks)?);
 
     Ok(())
 }
 
-fn part1(input: &str) -> u32 {
-    input
-        .lines()
-        .map(|s| {
-            let (l, r) = s.split_at(s.len() / 2);
-            (bits(l) & bits(r)).trailing_zeros()
-        })
-        .sum()
+/// Lists the items shared by each rucksack and group, with `--group <n>`
+/// elves per group and `--priority <standard|ascii>` items. With `--plan`,
+/// lists the moves sorting out each rucksack and the badge candidates of
+/// each group instead.
+fn analyze(input: &str, args: &[String]) -> Result<()> {
+    let mut analyzer = Analyzer::default();
+    let mut plan = false;
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let mut value = || args.next().ok_or(format!("missing value for `{flag}`"));
+        match flag.as_str() {
+            "--group" => {
+                analyzer.group = value()?.parse()?;
+                if analyzer.group == 0 {
+                    return Err("groups should have at least one elf".into());
+                }
+            }
+            "--priority" => {
+                analyzer.priority = match value()?.as_str() {
+                    "standard" => rucksack::standard,
+                    "ascii" => rucksack::ascii,
+                    p => return Err(format!("unknown priority `{p}`").into()),
+                }
+            }
+            "--plan" => plan = true,
+            _ => return Err(format!("unknown flag `{flag}`").into()),
+        }
+    }
+
+    let sacks = analyzer.parse(input)?;
+    if plan {
+        return reorganise(&analyzer, &sacks);
+    }
+    let show = |items: Items| match items.is_empty() {
+        true => "nothing".to_string(),
+        false => format!("{items} ({})", analyzer.score(items)),
+    };
+    for (i, s) in sacks.iter().enumerate() {
+        println!("{}: {}", i + 1, show(s.shared()));
+    }
+    for (i, badge) in analyzer.badges(&sacks)?.into_iter().enumerate() {
+        println!("group {}: {}", i + 1, show(badge));
+    }
+    println!("p1 = {}", part1(&analyzer, &sacks));
+    println!("p2 = {}", part2(&analyzer, &sacks)?);
+
+    Ok(())
 }
 
-fn part2(input: &str) -> u32 {
-    input
-        .lines()
-        .array_chunks::<3>()
-        .filter_map(|c| {
-            c.into_iter()
-                .map(bits)
-                .reduce(|l, r| l & r)
-                .map(|m| m.trailing_zeros())
-        })
-        .sum()
+fn reorganise(analyzer: &Analyzer, sacks: &[Rucksack]) -> Result<()> {
+    for (i, s) in sacks.iter().enumerate() {
+        for m in plan::reorganise(s) {
+            println!("{}: {m}", i + 1);
+        }
+    }
+    for g in plan::candidates(analyzer, sacks)? {
+        match g.badge() {
+            Some(badge) => println!("group at {}: badge `{}`", g.line, badge.escape_ascii()),
+            None if g.items.is_empty() => println!("group at {}: no candidates", g.line),
+            None => println!("group at {}: candidates `{}`", g.line, g.items),
+        }
+    }
+    Ok(())
 }
 
-fn bits(s: &str) -> u64 {
-    s.bytes().fold(0_u64, |acc, b| acc | (1 << prio(b)))
+fn part1(analyzer: &Analyzer, sacks: &[Rucksack]) -> u32 {
+    sacks.iter().map(|s| analyzer.score(s.shared())).sum()
 }
 
-fn prio(x: u8) -> u8 {
-    if x.is_ascii_lowercase() {
-        x - b'a' + 1
-    } else {
-        x - b'A' + 27
-    }
+fn part2(analyzer: &Analyzer, sacks: &[Rucksack]) -> Result<u32> {
+    let badges = analyzer.badges(sacks)?;
+    Ok(badges.into_iter().map(|b| analyzer.score(b)).sum())
 }
 
 #[cfg(test)]
@@ -63,11 +113,16 @@ CrZsJsPPZsGzwwsLwLmpwMDw"#;
 
     #[test]
     fn part1_example() {
-        assert_eq!(part1(INPUT), 157);
+        let analyzer = Analyzer::default();
+        assert_eq!(part1(&analyzer, &analyzer.parse(INPUT).unwrap()), 157);
     }
 
     #[test]
     fn part2_example() {
-        assert_eq!(part2(INPUT), 70);
+        let analyzer = Analyzer::default();
+        assert_eq!(
+            part2(&analyzer, &analyzer.parse(INPUT).unwrap()).unwrap(),
+            70
+        );
     }
 }
@@ -0,0 +1,155 @@
+use std::fmt;
+
+use crate::rucksack::{Analyzer, Items, Rucksack, RucksackError};
+
+/// Compartment of a rucksack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    First,
+    Second,
+}
+
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Side::First => write!(f, "first"),
+            Side::Second => write!(f, "second"),
+        }
+    }
+}
+
+/// Moves `count` copies of `item` out of compartment `from` into the other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Move {
+    pub item: u8,
+    pub count: usize,
+    pub from: Side,
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "move {} `{}` from {} compartment",
+            self.count,
+            self.item.escape_ascii(),
+            self.from
+        )
+    }
+}
+
+/// Fewest item moves leaving no item in both compartments: every shared
+/// item leaves the compartment with fewer copies of it, the second one on
+/// ties. Compartments may end up with different sizes.
+pub fn reorganise(sack: &Rucksack) -> Vec<Move> {
+    let (first, second) = sack.compartments();
+    let copies = |side: &[u8], x| side.iter().filter(|&&y| y == x).count();
+    sack.shared()
+        .iter()
+        .map(|item| match (copies(first, item), copies(second, item)) {
+            (l, r) if l < r => Move {
+                item,
+                count: l,
+                from: Side::First,
+            },
+            (_, r) => Move {
+                item,
+                count: r,
+                from: Side::Second,
+            },
+        })
+        .collect()
+}
+
+/// Badge candidates of a group: the items every elf in it carries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Candidates {
+    /// Line of the group's first rucksack, starting from 1.
+    pub line: usize,
+    pub items: Items,
+}
+
+impl Candidates {
+    /// The group's badge, if there's exactly one candidate.
+    pub fn badge(&self) -> Option<u8> {
+        (self.items.len() == 1)
+            .then(|| self.items.iter().next())
+            .flatten()
+    }
+}
+
+/// Candidates of every group, in order.
+pub fn candidates(
+    analyzer: &Analyzer,
+    sacks: &[Rucksack],
+) -> Result<Vec<Candidates>, RucksackError> {
+    let badges = analyzer.badges(sacks)?;
+    Ok(badges
+        .into_iter()
+        .enumerate()
+        .map(|(i, items)| Candidates {
+            line: i * analyzer.group + 1,
+            items,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Compartments after the moves.
+    fn apply(sack: &Rucksack, moves: &[Move]) -> (Vec<u8>, Vec<u8>) {
+        let (first, second) = sack.compartments();
+        let (mut first, mut second) = (first.to_vec(), second.to_vec());
+        for m in moves {
+            let (from, to) = match m.from {
+                Side::First => (&mut first, &mut second),
+                Side::Second => (&mut second, &mut first),
+            };
+            for _ in 0..m.count {
+                let i = from.iter().position(|&x| x == m.item).unwrap();
+                to.push(from.remove(i));
+            }
+        }
+        (first, second)
+    }
+
+    #[test]
+    fn moves() {
+        let analyzer = Analyzer::default();
+        let sacks = analyzer.parse("aabcxcaaba\nabcdef").unwrap();
+        let moves = reorganise(&sacks[0]);
+        assert_eq!(
+            moves.iter().map(Move::to_string).collect::<Vec<_>>(),
+            [
+                "move 2 `a` from first compartment",
+                "move 1 `b` from second compartment",
+                "move 1 `c` from second compartment",
+            ]
+        );
+        let (first, second) = apply(&sacks[0], &moves);
+        assert_eq!(first.len() + second.len(), 10);
+        let first = first.into_iter().collect::<Items>();
+        assert!((first & second.into_iter().collect()).is_empty());
+        assert!(reorganise(&sacks[1]).is_empty());
+    }
+
+    #[test]
+    fn groups() {
+        let analyzer = Analyzer {
+            group: 2,
+            ..Analyzer::default()
+        };
+        let sacks = analyzer.parse("abcd\nbcxy\nzZ\nqQ\naZ\nZq").unwrap();
+        let groups = candidates(&analyzer, &sacks).unwrap();
+        assert_eq!(groups.iter().map(|g| g.line).collect::<Vec<_>>(), [1, 3, 5]);
+        assert_eq!(groups[0].items.to_string(), "bc");
+        assert!(groups[1].items.is_empty());
+        assert_eq!(
+            groups.iter().map(Candidates::badge).collect::<Vec<_>>(),
+            [None, None, Some(b'Z')]
+        );
+        assert!(candidates(&analyzer, &sacks[1..]).is_err());
+    }
+}
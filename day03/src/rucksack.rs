@@ -0,0 +1,255 @@
+use std::error;
+use std::fmt;
+use std::ops::{BitAnd, BitOr};
+
+/// Priority of an item, or `None` if it's not a valid item.
+pub type Priority = fn(u8) -> Option<u32>;
+
+/// The puzzle's priorities: `a` to `z` are 1 to 26, `A` to `Z` 27 to 52.
+pub fn standard(x: u8) -> Option<u32> {
+    match x {
+        b'a'..=b'z' => Some((x - b'a') as u32 + 1),
+        b'A'..=b'Z' => Some((x - b'A') as u32 + 27),
+        _ => None,
+    }
+}
+
+/// Any printable ASCII character, by its position after the space.
+pub fn ascii(x: u8) -> Option<u32> {
+    x.is_ascii_graphic().then(|| (x - b' ') as u32)
+}
+
+/// Set of items, one bit per byte value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Items([u64; 4]);
+
+impl Items {
+    pub fn insert(&mut self, x: u8) {
+        self.0[x as usize / 64] |= 1 << (x % 64);
+    }
+
+    pub fn contains(&self, x: u8) -> bool {
+        self.0[x as usize / 64] >> (x % 64) & 1 == 1
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == [0; 4]
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = u8> + '_ {
+        (0..=u8::MAX).filter(|&x| self.contains(x))
+    }
+}
+
+impl FromIterator<u8> for Items {
+    fn from_iter<T: IntoIterator<Item = u8>>(iter: T) -> Self {
+        let mut items = Items::default();
+        for x in iter {
+            items.insert(x);
+        }
+        items
+    }
+}
+
+impl BitAnd for Items {
+    type Output = Items;
+
+    fn bitand(self, rhs: Items) -> Items {
+        Items([0, 1, 2, 3].map(|i| self.0[i] & rhs.0[i]))
+    }
+}
+
+impl BitOr for Items {
+    type Output = Items;
+
+    fn bitor(self, rhs: Items) -> Items {
+        Items([0, 1, 2, 3].map(|i| self.0[i] | rhs.0[i]))
+    }
+}
+
+impl fmt::Display for Items {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.iter()
+            .try_for_each(|x| write!(f, "{}", x.escape_ascii()))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RucksackErrorKind {
+    /// Items can't be split evenly between the two compartments.
+    OddLength(usize),
+    InvalidItem(u8),
+    /// Last group with fewer rucksacks than the others.
+    IncompleteGroup(usize),
+}
+
+/// Error in the list of rucksacks, with a line number starting from 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RucksackError {
+    pub line: usize,
+    pub kind: RucksackErrorKind,
+}
+
+impl fmt::Display for RucksackError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match self.kind {
+            RucksackErrorKind::OddLength(n) => write!(f, "{n} items can't be split in two"),
+            RucksackErrorKind::InvalidItem(x) => write!(f, "invalid item `{}`", x.escape_ascii()),
+            RucksackErrorKind::IncompleteGroup(n) => write!(f, "group of only {n} rucksacks"),
+        }
+    }
+}
+
+impl error::Error for RucksackError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rucksack {
+    items: Vec<u8>,
+}
+
+impl Rucksack {
+    pub fn compartments(&self) -> (&[u8], &[u8]) {
+        self.items.split_at(self.items.len() / 2)
+    }
+
+    pub fn contents(&self) -> Items {
+        self.items.iter().copied().collect()
+    }
+
+    /// Items found in both compartments.
+    pub fn shared(&self) -> Items {
+        let (l, r) = self.compartments();
+        l.iter().copied().collect::<Items>() & r.iter().copied().collect()
+    }
+}
+
+/// How rucksacks are checked and scored.
+#[derive(Debug, Clone, Copy)]
+pub struct Analyzer {
+    pub priority: Priority,
+    /// Number of elves in a group.
+    pub group: usize,
+}
+
+impl Default for Analyzer {
+    fn default() -> Self {
+        Self {
+            priority: standard,
+            group: 3,
+        }
+    }
+}
+
+impl Analyzer {
+    /// Reads a rucksack per line, making sure every item is valid.
+    pub fn parse(&self, input: &str) -> Result<Vec<Rucksack>, RucksackError> {
+        input
+            .lines()
+            .enumerate()
+            .map(|(i, l)| {
+                let err = |kind| RucksackError { line: i + 1, kind };
+                let items = l.as_bytes();
+                if let Some(&x) = items.iter().find(|&&x| (self.priority)(x).is_none()) {
+                    return Err(err(RucksackErrorKind::InvalidItem(x)));
+                }
+                if !items.len().is_multiple_of(2) {
+                    return Err(err(RucksackErrorKind::OddLength(items.len())));
+                }
+                Ok(Rucksack {
+                    items: items.to_vec(),
+                })
+            })
+            .collect()
+    }
+
+    /// Sum of the priorities of every item.
+    pub fn score(&self, items: Items) -> u32 {
+        items.iter().filter_map(self.priority).sum()
+    }
+
+    /// Items carried by everyone in each group.
+    pub fn badges(&self, sacks: &[Rucksack]) -> Result<Vec<Items>, RucksackError> {
+        assert!(self.group > 0, "groups should have at least one elf");
+        if !sacks.len().is_multiple_of(self.group) {
+            return Err(RucksackError {
+                line: sacks.len() - sacks.len() % self.group + 1,
+                kind: RucksackErrorKind::IncompleteGroup(sacks.len() % self.group),
+            });
+        }
+        Ok(sacks
+            .chunks(self.group)
+            .map(|g| {
+                g.iter()
+                    .map(Rucksack::contents)
+                    .reduce(BitAnd::bitand)
+                    .unwrap_or_default()
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn items() {
+        let items = b"zaZ\xff ".iter().copied().collect::<Items>();
+        assert_eq!(
+            items.iter().collect::<Vec<_>>(),
+            [b' ', b'Z', b'a', b'z', 0xff]
+        );
+        assert_eq!(items.to_string(), " Zaz\\xff");
+        let other = b"aq".iter().copied().collect::<Items>();
+        assert_eq!((items & other).to_string(), "a");
+        assert_eq!((items | other).iter().count(), 6);
+        assert!((other & Items::default()).is_empty());
+    }
+
+    #[test]
+    fn shared_items() {
+        let analyzer = Analyzer::default();
+        let sacks = analyzer.parse("abcCab\nxyzXYZ").unwrap();
+        assert_eq!(sacks[0].shared().to_string(), "ab");
+        assert_eq!(analyzer.score(sacks[0].shared()), 3);
+        assert!(sacks[1].shared().is_empty());
+    }
+
+    #[test]
+    fn errors() {
+        let analyzer = Analyzer::default();
+        let err = analyzer.parse("abab\nabc").unwrap_err();
+        assert_eq!(
+            err,
+            RucksackError {
+                line: 2,
+                kind: RucksackErrorKind::OddLength(3),
+            }
+        );
+        let err = analyzer.parse("a-").unwrap_err();
+        assert_eq!(err.kind, RucksackErrorKind::InvalidItem(b'-'));
+
+        let analyzer = Analyzer {
+            priority: ascii,
+            group: 2,
+        };
+        let sacks = analyzer.parse("a-\n-b\n+-").unwrap();
+        assert_eq!(analyzer.score(sacks[0].shared()), 0);
+        let err = analyzer.badges(&sacks).unwrap_err();
+        assert_eq!(
+            err,
+            RucksackError {
+                line: 3,
+                kind: RucksackErrorKind::IncompleteGroup(1),
+            }
+        );
+        let badges = analyzer.badges(&sacks[..2]).unwrap();
+        assert_eq!(badges, [b"-".iter().copied().collect()]);
+        assert_eq!(analyzer.score(badges[0]), 13);
+    }
+}
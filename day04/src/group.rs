@@ -0,0 +1,103 @@
+use std::str::FromStr;
+
+use crate::interval::{Interval, IntervalSet};
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+
+/// Section assignments of a group of elves, one interval each.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Group {
+    pub elves: Vec<Interval>,
+}
+
+/// Parses comma separated assignments, e.g. `2-4,6-8,3-5`.
+impl FromStr for Group {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let elves = s
+            .split(',')
+            .map(str::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Group { elves })
+    }
+}
+
+impl Group {
+    /// Sections someone is assigned to.
+    pub fn coverage(&self) -> IntervalSet {
+        self.elves.iter().copied().collect()
+    }
+
+    /// Sections assigned to more than one elf.
+    pub fn overlap(&self) -> IntervalSet {
+        let mut seen = IntervalSet::new();
+        let mut twice = IntervalSet::new();
+        for &e in &self.elves {
+            let e = IntervalSet::from(e);
+            twice = twice.union(&seen.intersection(&e));
+            seen = seen.union(&e);
+        }
+        twice
+    }
+
+    /// Indices of the elves whose sections are all assigned to others too.
+    /// Each is redundant on its own, but not necessarily along with the rest.
+    pub fn redundant(&self) -> Vec<usize> {
+        (0..self.elves.len())
+            .filter(|&i| {
+                let others = self
+                    .elves
+                    .iter()
+                    .enumerate()
+                    .filter(|&(j, _)| j != i)
+                    .map(|(_, &e)| e)
+                    .collect::<IntervalSet>();
+                others.covers(&self.elves[i])
+            })
+            .collect()
+    }
+
+    /// Sections nobody is assigned to, between the lowest and highest ones
+    /// that are.
+    pub fn uncovered(&self) -> IntervalSet {
+        let coverage = self.coverage();
+        coverage
+            .span()
+            .map(|span| IntervalSet::from(span).difference(&coverage))
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pairs() {
+        let g = "2-8,3-7".parse::<Group>().unwrap();
+        assert_eq!(g.overlap().to_string(), "3-7");
+        assert_eq!(g.redundant(), [1]);
+        assert!(g.uncovered().is_empty());
+
+        let g = "2-4,6-8".parse::<Group>().unwrap();
+        assert!(g.overlap().is_empty());
+        assert!(g.redundant().is_empty());
+        assert_eq!(g.uncovered().to_string(), "5-5");
+    }
+
+    #[test]
+    fn many_elves() {
+        let g = "1-4,3-6,10-12,5-5,11-20,30-30".parse::<Group>().unwrap();
+        assert_eq!(g.coverage().to_string(), "1-6,10-20,30-30");
+        assert_eq!(g.overlap().to_string(), "3-5,11-12");
+        // Only the fourth elf has no section of its own.
+        assert_eq!(g.redundant(), [3]);
+        assert_eq!(g.uncovered().to_string(), "7-9,21-29");
+
+        // Identical assignments are redundant given each other.
+        let g = "1-3,1-3,2-2".parse::<Group>().unwrap();
+        assert_eq!(g.redundant(), [0, 1, 2]);
+        assert!("1-3,".parse::<Group>().is_err());
+    }
+}
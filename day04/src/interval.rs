@@ -0,0 +1,297 @@
+use std::fmt;
+use std::str::FromStr;
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+
+/// Inclusive range of integers, never empty.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Interval {
+    pub start: i64,
+    pub end: i64,
+}
+
+impl Interval {
+    pub fn new(start: i64, end: i64) -> Self {
+        assert!(start <= end, "interval {start}-{end} is empty");
+        Self { start, end }
+    }
+
+    /// Number of integers in the interval.
+    pub fn size(&self) -> u64 {
+        self.start.abs_diff(self.end) + 1
+    }
+
+    pub fn contains(&self, x: i64) -> bool {
+        self.start <= x && x <= self.end
+    }
+
+    /// Whether all of `other` is within this one.
+    pub fn covers(&self, other: &Interval) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+
+    pub fn overlaps(&self, other: &Interval) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+
+    pub fn intersection(&self, other: &Interval) -> Option<Interval> {
+        self.overlaps(other)
+            .then(|| Interval::new(self.start.max(other.start), self.end.min(other.end)))
+    }
+
+    pub fn overlap_len(&self, other: &Interval) -> u64 {
+        self.intersection(other).map_or(0, |i| i.size())
+    }
+}
+
+/// Parses `<start>-<end>`, either of which may be negative.
+impl FromStr for Interval {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let dash = s
+            .get(1..)
+            .and_then(|rest| rest.find('-'))
+            .ok_or_else(|| format!("invalid interval `{s}`"))?
+            + 1;
+        let (start, end) = (s[..dash].trim().parse()?, s[dash + 1..].trim().parse()?);
+        if start > end {
+            return Err(format!("interval `{s}` is empty").into());
+        }
+        Ok(Interval::new(start, end))
+    }
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+/// Set of integers as sorted intervals, merged so that none of them overlap
+/// or touch.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct IntervalSet {
+    intervals: Vec<Interval>,
+}
+
+impl IntervalSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    /// Number of integers in the set.
+    pub fn len(&self) -> u64 {
+        self.intervals.iter().map(Interval::size).sum()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Interval> {
+        self.intervals.iter()
+    }
+
+    /// Smallest interval holding the whole set.
+    pub fn span(&self) -> Option<Interval> {
+        let (first, last) = (self.intervals.first()?, self.intervals.last()?);
+        Some(Interval::new(first.start, last.end))
+    }
+
+    pub fn contains(&self, x: i64) -> bool {
+        let i = self.intervals.partition_point(|i| i.end < x);
+        self.intervals.get(i).is_some_and(|i| i.contains(x))
+    }
+
+    pub fn covers(&self, other: &Interval) -> bool {
+        let i = self.intervals.partition_point(|i| i.end < other.start);
+        self.intervals.get(i).is_some_and(|i| i.covers(other))
+    }
+
+    pub fn insert(&mut self, interval: Interval) {
+        // Everything from `lo` to `hi` overlaps or touches the new interval.
+        let lo = self
+            .intervals
+            .partition_point(|i| i.end.saturating_add(1) < interval.start);
+        let hi = self
+            .intervals
+            .partition_point(|i| i.start <= interval.end.saturating_add(1));
+        let merged = self.intervals[lo..hi].iter().fold(interval, |m, i| {
+            Interval::new(m.start.min(i.start), m.end.max(i.end))
+        });
+        self.intervals.splice(lo..hi, [merged]);
+    }
+
+    pub fn union(&self, other: &IntervalSet) -> IntervalSet {
+        self.iter().chain(other.iter()).copied().collect()
+    }
+
+    pub fn intersection(&self, other: &IntervalSet) -> IntervalSet {
+        let mut out = Vec::new();
+        let (mut a, mut b) = (self.iter().peekable(), other.iter().peekable());
+        while let (Some(x), Some(y)) = (a.peek(), b.peek()) {
+            out.extend(x.intersection(y));
+            if x.end < y.end {
+                a.next();
+            } else {
+                b.next();
+            }
+        }
+        IntervalSet { intervals: out }
+    }
+
+    pub fn difference(&self, other: &IntervalSet) -> IntervalSet {
+        let cuts = &other.intervals;
+        let mut out = Vec::new();
+        let mut k = 0;
+        for &i in &self.intervals {
+            while cuts.get(k).is_some_and(|c| c.end < i.start) {
+                k += 1;
+            }
+            // What's left of `i` starts at `start`, and the cut at `k` never
+            // ends before it.
+            let mut start = i.start;
+            loop {
+                match cuts.get(k) {
+                    Some(c) if c.start <= i.end => {
+                        if c.start > start {
+                            out.push(Interval::new(start, c.start - 1));
+                        }
+                        if c.end >= i.end {
+                            break;
+                        }
+                        start = c.end + 1;
+                        k += 1;
+                    }
+                    _ => {
+                        out.push(Interval::new(start, i.end));
+                        break;
+                    }
+                }
+            }
+        }
+        IntervalSet { intervals: out }
+    }
+}
+
+/// Merges intervals in any order.
+impl FromIterator<Interval> for IntervalSet {
+    fn from_iter<T: IntoIterator<Item = Interval>>(iter: T) -> Self {
+        let mut intervals = iter.into_iter().collect::<Vec<_>>();
+        intervals.sort_unstable();
+        let mut merged: Vec<Interval> = Vec::with_capacity(intervals.len());
+        for i in intervals {
+            match merged.last_mut() {
+                Some(m) if i.start <= m.end.saturating_add(1) => m.end = m.end.max(i.end),
+                _ => merged.push(i),
+            }
+        }
+        IntervalSet { intervals: merged }
+    }
+}
+
+impl From<Interval> for IntervalSet {
+    fn from(i: Interval) -> Self {
+        IntervalSet { intervals: vec![i] }
+    }
+}
+
+impl fmt::Display for IntervalSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (k, i) in self.intervals.iter().enumerate() {
+            if k > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{i}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    fn set(s: &str) -> IntervalSet {
+        s.split(',')
+            .map(|i| i.parse::<Interval>().unwrap())
+            .collect()
+    }
+
+    fn points(s: &IntervalSet) -> BTreeSet<i64> {
+        s.iter().flat_map(|i| i.start..=i.end).collect()
+    }
+
+    #[test]
+    fn intervals() {
+        let a = "2-8".parse::<Interval>().unwrap();
+        let b = "-3--1".parse::<Interval>().unwrap();
+        assert_eq!(b, Interval::new(-3, -1));
+        assert_eq!(a.size(), 7);
+        assert!(a.covers(&Interval::new(3, 7)));
+        assert!(!a.covers(&Interval::new(3, 9)));
+        assert!(!a.overlaps(&b));
+        assert_eq!(
+            a.intersection(&Interval::new(6, 10)),
+            Some(Interval::new(6, 8))
+        );
+        assert_eq!(a.overlap_len(&Interval::new(8, 10)), 1);
+        assert!("5-4".parse::<Interval>().is_err());
+        assert!("5".parse::<Interval>().is_err());
+    }
+
+    #[test]
+    fn sets() {
+        let s = set("5-7,1-2,3-4,10-12,11-20");
+        assert_eq!(s.to_string(), "1-7,10-20");
+        assert_eq!(s.len(), 18);
+        assert_eq!(s.span(), Some(Interval::new(1, 20)));
+        assert!(s.contains(10) && !s.contains(8));
+        assert!(s.covers(&Interval::new(2, 6)) && !s.covers(&Interval::new(6, 10)));
+
+        let t = set("0-1,4-11");
+        assert_eq!(s.union(&t).to_string(), "0-20");
+        assert_eq!(s.intersection(&t).to_string(), "1-1,4-7,10-11");
+        assert_eq!(s.difference(&t).to_string(), "2-3,12-20");
+        assert_eq!(t.difference(&s).to_string(), "0-0,8-9");
+    }
+
+    #[test]
+    fn matches_point_sets() {
+        // Every set made of up to two intervals within 0-4, inserted in
+        // either order, so they overlap, touch or are apart.
+        let intervals = (0..=4)
+            .flat_map(|start| (start..=4).map(move |end| Interval::new(start, end)))
+            .collect::<Vec<_>>();
+        let mut sets = vec![IntervalSet::new()];
+        for &i in &intervals {
+            sets.push([i].into_iter().collect());
+            for &j in &intervals {
+                let mut s = IntervalSet::new();
+                s.insert(i);
+                s.insert(j);
+                sets.push(s);
+            }
+        }
+
+        for (a, b) in sets.iter().flat_map(|a| sets.iter().map(move |b| (a, b))) {
+            let (pa, pb) = (points(a), points(b));
+            for s in [a, b] {
+                // Merged, sorted and apart from each other.
+                assert!(s.intervals.windows(2).all(|w| w[0].end + 1 < w[1].start));
+            }
+            assert_eq!(points(&a.union(b)), &pa | &pb);
+            assert_eq!(points(&a.intersection(b)), &pa & &pb);
+            assert_eq!(points(&a.difference(b)), &pa - &pb);
+            assert_eq!(a.len(), pa.len() as u64);
+            assert_eq!(a.union(b), a.iter().chain(b.iter()).copied().collect());
+            for x in -1..6 {
+                assert_eq!(a.contains(x), pa.contains(&x));
+                let i = Interval::new(x, x + 2);
+                assert_eq!(a.covers(&i), (x..=x + 2).all(|x| pa.contains(&x)));
+            }
+        }
+    }
+}
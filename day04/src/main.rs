@@ -1,5 +1,8 @@
+use std::env;
 use std::io::{self, Read};
 
+use day04::group::Group;
+
 type Error = Box<dyn std::error::Error + Send + Sync>;
 type Result<T, E = Error> = std::result::Result<T, E>;
 
@@ -7,40 +10,60 @@ fn main() -> Result<()> {
     let mut input = String::new();
     io::stdin().read_to_string(&mut input)?;
 
+    match env::args().nth(1).as_deref() {
+        Some("--report") => return report(&input),
+        Some(arg) => return Err(format!("unknown argument `{arg}`").into()),
+        None => {}
+    }
+
     println!("p1 = {}", part1(&input));
     println!("p2 = {}", part2(&input));
 
     Ok(())
 }
 
+/// Prints, for every group of any number of elves, the sections assigned
+/// more than once, the elves that could be spared and the gaps in between.
+fn report(input: &str) -> Result<()> {
+    for (i, l) in input.lines().enumerate() {
+        let g = l.parse::<Group>()?;
+        let redundant = g
+            .redundant()
+            .iter()
+            .map(|e| (e + 1).to_string())
+            .collect::<Vec<_>>();
+        println!(
+            "{}: overlap [{}], redundant [{}], uncovered [{}]",
+            i + 1,
+            g.overlap(),
+            redundant.join(","),
+            g.uncovered()
+        );
+    }
+
+    Ok(())
+}
+
 fn part1(input: &str) -> usize {
-    count(input, |(b0, e0, b1, e1)| b0 <= b1 && e0 >= e1 || b0 >= b1 && e0 <= e1)
+    // With two elves, one is redundant exactly when it's within the other.
+    count(input, |g| !g.redundant().is_empty())
 }
 
 fn part2(input: &str) -> usize {
-    count(input, |(b0, e0, b1, e1)| b0 <= e1 && e0 >= b1)
+    count(input, |g| !g.overlap().is_empty())
 }
 
 fn count<F>(input: &str, f: F) -> usize
 where
-    F: Fn(&(u32, u32, u32, u32)) -> bool,
+    F: Fn(&Group) -> bool,
 {
     input
         .lines()
-        .filter_map(try_parse_intervals)
+        .filter_map(|l| l.parse::<Group>().ok())
         .filter(f)
         .count()
 }
 
-fn try_parse_intervals(s: &str) -> Option<(u32, u32, u32, u32)> {
-    let mut parts = s.split([',', '-']);
-    let b0 = parts.next()?.parse().ok()?;
-    let e0 = parts.next()?.parse().ok()?;
-    let b1 = parts.next()?.parse().ok()?;
-    let e1 = parts.next()?.parse().ok()?;
-    Some((b0, e0, b1, e1))
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,291 @@
+use std::error;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::stacks::Stacks;
+use crate::{Error, Result};
+
+/// `move <count> from <from> to <to>`, with stacks numbered from 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Move {
+    pub count: usize,
+    pub from: usize,
+    pub to: usize,
+}
+
+impl FromStr for Move {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let [count, from, to] = match s.split_whitespace().collect::<Vec<_>>()[..] {
+            ["move", count, "from", from, "to", to] => [count, from, to],
+            _ => return Err(format!("invalid move `{s}`").into()),
+        };
+        let stack = |n: &str| match n.parse::<usize>()? {
+            0 => Err(Error::from(format!("stacks are numbered from 1 in `{s}`"))),
+            n => Ok(n - 1),
+        };
+        Ok(Move {
+            count: count.parse()?,
+            from: stack(from)?,
+            to: stack(to)?,
+        })
+    }
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "move {} from {} to {}",
+            self.count,
+            self.from + 1,
+            self.to + 1
+        )
+    }
+}
+
+/// Model of crane, deciding how a bunch of crates end up on the target stack.
+pub trait Crane {
+    /// Rearranges `crates`, lifted off a stack and listed bottom to top, into
+    /// the order they're in once they've landed.
+    fn carry(&self, crates: &mut [char]);
+}
+
+/// Moves crates one at a time, so they land upside down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+    fn carry(&self, crates: &mut [char]) {
+        crates.reverse();
+    }
+}
+
+/// Moves all the crates at once, keeping their order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CrateMover9001;
+
+impl Crane for CrateMover9001 {
+    fn carry(&self, _: &mut [char]) {}
+}
+
+/// Moves up to the given number of crates at a time, taking them from the top.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limited(pub usize);
+
+impl Crane for Limited {
+    fn carry(&self, crates: &mut [char]) {
+        // Batches land in the reverse order of how they sit on the stack,
+        // each keeping its own order.
+        crates.reverse();
+        for batch in crates.chunks_mut(self.0.max(1)) {
+            batch.reverse();
+        }
+    }
+}
+
+/// Move that can't be carried out, with its line in the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MoveError {
+    pub line: usize,
+    pub kind: MoveErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MoveErrorKind {
+    NoStack(usize),
+    TooMany {
+        stack: usize,
+        has: usize,
+        count: usize,
+    },
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            MoveErrorKind::NoStack(s) => write!(f, "no stack {}", s + 1),
+            MoveErrorKind::TooMany { stack, has, count } => write!(
+                f,
+                "cannot take {count} crates from stack {}, which has {has}",
+                stack + 1
+            ),
+        }
+    }
+}
+
+impl error::Error for MoveError {}
+
+/// Stacks being rearranged by a crane, remembering what each move lifted so
+/// it can be undone.
+#[derive(Debug, Clone)]
+pub struct Simulator<C> {
+    crane: C,
+    stacks: Stacks,
+    done: Vec<(Move, Vec<char>)>,
+    undone: Vec<Move>,
+}
+
+impl<C: Crane> Simulator<C> {
+    pub fn new(crane: C, stacks: Stacks) -> Self {
+        Self {
+            crane,
+            stacks,
+            done: Vec::new(),
+            undone: Vec::new(),
+        }
+    }
+
+    pub fn stacks(&self) -> &Stacks {
+        &self.stacks
+    }
+
+    fn check(&self, m: Move) -> Result<(), MoveErrorKind> {
+        let has = self
+            .stacks
+            .get(m.from)
+            .ok_or(MoveErrorKind::NoStack(m.from))?
+            .len();
+        if m.to >= self.stacks.len() {
+            return Err(MoveErrorKind::NoStack(m.to));
+        }
+        if m.count > has {
+            return Err(MoveErrorKind::TooMany {
+                stack: m.from,
+                has,
+                count: m.count,
+            });
+        }
+        Ok(())
+    }
+
+    fn step(&mut self, m: Move) {
+        let lifted = self.stacks.lift(m.from, m.count);
+        let mut crates = lifted.clone();
+        self.crane.carry(&mut crates);
+        self.stacks.place(m.to, crates);
+        self.done.push((m, lifted));
+    }
+
+    /// Carries out a move, which can't be redone past anymore.
+    pub fn apply(&mut self, m: Move) -> Result<(), MoveErrorKind> {
+        self.check(m)?;
+        self.step(m);
+        self.undone.clear();
+        Ok(())
+    }
+
+    /// Applies `moves`, paired with their lines in the input, in order,
+    /// calling `each` with every move and the stacks right after it. Stops at
+    /// the first invalid move.
+    pub fn run<I, F>(&mut self, moves: I, mut each: F) -> Result<(), MoveError>
+    where
+        I: IntoIterator<Item = (usize, Move)>,
+        F: FnMut(Move, &Stacks),
+    {
+        for (line, m) in moves {
+            self.apply(m).map_err(|kind| MoveError { line, kind })?;
+            each(m, &self.stacks);
+        }
+        Ok(())
+    }
+
+    /// Puts the crates of the last move back where they were.
+    pub fn undo(&mut self) -> Option<Move> {
+        let (m, lifted) = self.done.pop()?;
+        self.stacks.lift(m.to, m.count);
+        self.stacks.place(m.from, lifted);
+        self.undone.push(m);
+        Some(m)
+    }
+
+    /// Carries out the last undone move again.
+    pub fn redo(&mut self) -> Option<Move> {
+        let m = self.undone.pop()?;
+        self.step(m);
+        Some(m)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stacks() -> Stacks {
+        "    [D]\n[N] [C]\n[Z] [M] [P]\n 1   2   3".parse().unwrap()
+    }
+
+    fn carried(crane: impl Crane) -> String {
+        let mut crates = ['A', 'B', 'C', 'D', 'E'];
+        crane.carry(&mut crates);
+        String::from_iter(crates)
+    }
+
+    #[test]
+    fn moves() {
+        let m = "move 3 from 1 to 9".parse::<Move>().unwrap();
+        assert_eq!(
+            m,
+            Move {
+                count: 3,
+                from: 0,
+                to: 8
+            }
+        );
+        assert_eq!(m.to_string(), "move 3 from 1 to 9");
+        assert!("move 1 from 0 to 1".parse::<Move>().is_err());
+        assert!("move 1 to 2".parse::<Move>().is_err());
+    }
+
+    #[test]
+    fn cranes() {
+        assert_eq!(carried(CrateMover9000), "EDCBA");
+        assert_eq!(carried(CrateMover9001), "ABCDE");
+        assert_eq!(carried(Limited(1)), "EDCBA");
+        assert_eq!(carried(Limited(2)), "DEBCA");
+        assert_eq!(carried(Limited(5)), "ABCDE");
+    }
+
+    #[test]
+    fn undo_redo() {
+        let mut sim = Simulator::new(CrateMover9000, stacks());
+        sim.apply("move 2 from 2 to 1".parse().unwrap()).unwrap();
+        sim.apply("move 3 from 1 to 3".parse().unwrap()).unwrap();
+        assert_eq!(sim.stacks().tops(), "ZMN");
+
+        assert_eq!(sim.undo().unwrap().to_string(), "move 3 from 1 to 3");
+        assert_eq!(sim.stacks().tops(), "CMP");
+        sim.undo().unwrap();
+        assert_eq!(sim.stacks, stacks());
+        assert_eq!(sim.undo(), None);
+
+        sim.redo().unwrap();
+        sim.redo().unwrap();
+        assert_eq!(sim.stacks().tops(), "ZMN");
+        assert_eq!(sim.redo(), None);
+
+        // A new move drops whatever was left to redo.
+        sim.undo().unwrap();
+        sim.apply("move 1 from 1 to 1".parse().unwrap()).unwrap();
+        assert_eq!(sim.redo(), None);
+    }
+
+    #[test]
+    fn validation() {
+        let mut sim = Simulator::new(CrateMover9001, stacks());
+        let err = sim.apply("move 4 from 2 to 1".parse().unwrap());
+        assert_eq!(
+            err,
+            Err(MoveErrorKind::TooMany {
+                stack: 1,
+                has: 3,
+                count: 4
+            })
+        );
+        let err = sim.apply("move 1 from 1 to 4".parse().unwrap());
+        assert_eq!(err, Err(MoveErrorKind::NoStack(3)));
+        assert_eq!(sim.stacks, stacks());
+    }
+}
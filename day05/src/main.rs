@@ -1,8 +1,15 @@
-#![feature(iter_array_chunks)]
-#![feature(map_many_mut)]
-
-use std::collections::HashMap;
+use std::env;
 use std::io::{self, Read};
+use std::thread;
+use std::time::Duration;
+
+mod crane;
+mod stacks;
+mod stats;
+
+use crane::{Crane, CrateMover9000, CrateMover9001, Limited, Move, Simulator};
+use stacks::Stacks;
+use stats::Stats;
 
 type Error = Box<dyn std::error::Error + Send + Sync>;
 type Result<T, E = Error> = std::result::Result<T, E>;
@@ -11,72 +18,142 @@ fn main() -> Result<()> {
     let mut input = String::new();
     io::stdin().read_to_string(&mut input)?;
 
-    println!("p1 = {}", part1(&input));
-    println!("p2 = {}", part2(&input));
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    if !args.is_empty() {
+        return simulate(&input, &args);
+    }
+
+    println!("p1 = {}", part1(&input)?);
+    println!("p2 = {}", part2(&input)?);
 
     Ok(())
 }
 
-fn part1(input: &str) -> String {
-    solve::<true>(input)
+/// Runs every move with `--crane <9000|9001|n>`, the last one moving up to
+/// `n` crates at a time, then steps back with `--undo <n>` and forward again
+/// with `--redo <n>`, drawing the stacks at the end. `--animate <ms>` draws
+/// them after every move too, and `--stats` reports how the stacks changed.
+fn simulate(input: &str, args: &[String]) -> Result<()> {
+    let mut args = args.iter();
+    let model = match args.next().map(String::as_str) {
+        Some("--crane") => args.next().ok_or("missing value for `--crane`")?,
+        _ => return Err("expected `--crane <9000|9001|n>` first".into()),
+    };
+    let rest = args.as_slice();
+    match model.as_str() {
+        "9000" => replay(input, CrateMover9000, rest),
+        "9001" => replay(input, CrateMover9001, rest),
+        n => replay(input, Limited(n.parse()?), rest),
+    }
 }
 
-fn part2(input: &str) -> String {
-    solve::<false>(input)
-}
+fn replay(input: &str, crane: impl Crane, args: &[String]) -> Result<()> {
+    let mut steps = Vec::new();
+    let mut delay = None;
+    let mut show_stats = false;
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let mut value = || args.next().ok_or(format!("missing value for `{flag}`"));
+        match flag.as_str() {
+            "--undo" | "--redo" => steps.push((flag, value()?.parse::<usize>()?)),
+            "--animate" => delay = Some(Duration::from_millis(value()?.parse()?)),
+            "--stats" => show_stats = true,
+            _ => return Err(format!("unknown flag `{flag}`").into()),
+        }
+    }
 
-fn solve<const REV: bool>(input: &str) -> String {
-    let (image, moves) = input.split_once(" 1").expect("Wrong input format.");
-    moves
-        .lines()
-        .skip(2)
-        .filter_map(try_parse_move)
-        .try_fold(parse_stacks(image), |mut acc, (count, from, to)| {
-            let [stack_from, stack_to] = acc.get_many_mut([&from, &to])?;
-            if REV {
-                stack_to.extend(stack_from.drain(stack_from.len() - count..).rev());
+    let (stacks, moves) = parse(input)?;
+    let mut stats = Stats::new(&stacks);
+    let mut sim = Simulator::new(crane, stacks);
+    if let Some(delay) = delay {
+        draw("Initial state", sim.stacks(), delay);
+    }
+    sim.run(moves.iter().copied(), |m, stacks| {
+        stats.record(m);
+        if let Some(delay) = delay {
+            draw(
+                &format!("{} ({}/{})", m, stats.moves(), moves.len()),
+                stacks,
+                delay,
+            );
+        }
+    })?;
+
+    for (flag, n) in steps {
+        for _ in 0..n {
+            let m = if flag == "--undo" {
+                stats.undo();
+                sim.undo()
             } else {
-                stack_to.extend(stack_from.drain(stack_from.len() - count..));
+                sim.redo().inspect(|&m| stats.record(m))
+            };
+            match m {
+                Some(m) => println!("{flag}: {m}"),
+                None => return Err(format!("nothing left for `{flag}`").into()),
             }
-            Some(acc)
-        })
-        .map(|m| {
-            String::from_iter(
-                (0..m.len())
-                    .filter_map(|i| m.get(&i).and_then(|v| v.last()).copied())
-                    .map(Into::<char>::into),
-            )
-        })
-        .unwrap_or_default()
+        }
+    }
+    println!("{}", sim.stacks());
+    println!("tops: {}", sim.stacks().tops());
+
+    if show_stats {
+        println!("stack\tmax\ttaken\treceived\ttop from move");
+        for (i, top) in stats.top_arrivals().into_iter().enumerate() {
+            let top = top.map_or("-".to_string(), |m| m.to_string());
+            println!(
+                "{}\t{}\t{}\t{}\t{top}",
+                i + 1,
+                stats.max_height[i],
+                stats.taken[i],
+                stats.received[i]
+            );
+        }
+        let max = stats.max_height.iter().max().unwrap_or(&0);
+        println!("highest stack: {max}");
+    }
+
+    Ok(())
+}
+
+/// Draws a frame of the animation over the previous one.
+fn draw(title: &str, stacks: &Stacks, delay: Duration) {
+    println!("\x1b[2J\x1b[H== {title} ==\n{stacks}");
+    thread::sleep(delay);
+}
+
+fn part1(input: &str) -> Result<String> {
+    Ok(run(input, CrateMover9000)?.stacks().tops())
 }
 
-fn try_parse_move(input: &str) -> Option<(usize, usize, usize)> {
-    input
-        .split_whitespace()
-        .skip(1)
-        .step_by(2)
-        .filter_map(|s| s.parse::<usize>().ok())
-        .array_chunks()
-        .map(|[n, f, t]| (n, f - 1, t - 1))
-        .next()
+fn part2(input: &str) -> Result<String> {
+    Ok(run(input, CrateMover9001)?.stacks().tops())
 }
 
-fn parse_stacks(input: &str) -> HashMap<usize, Vec<u8>> {
-    input
+/// Parses the stacks and the moves, each paired with its line.
+fn parse(input: &str) -> Result<(Stacks, Vec<(usize, Move)>)> {
+    let (image, moves) = input.split_once("\n\n").ok_or("missing moves")?;
+    // Moves start after the drawing and the blank line.
+    let skip = image.lines().count() + 1;
+    let moves = moves
         .lines()
-        .rev()
-        .flat_map(|s| {
-            s.as_bytes()
-                .iter()
-                .skip(1)
-                .step_by(4)
-                .enumerate()
-                .filter(|(_, c)| c.is_ascii_uppercase())
-        })
-        .fold(HashMap::new(), |mut acc, (i, &c)| {
-            acc.entry(i).or_insert_with(Vec::new).push(c);
-            acc
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty())
+        .map(|(i, l)| {
+            let line = skip + i + 1;
+            let m = l
+                .parse()
+                .map_err(|e| Error::from(format!("line {line}: {e}")))?;
+            Ok((line, m))
         })
+        .collect::<Result<_>>()?;
+    Ok((image.parse()?, moves))
+}
+
+fn run<C: Crane>(input: &str, crane: C) -> Result<Simulator<C>> {
+    let (stacks, moves) = parse(input)?;
+    let mut sim = Simulator::new(crane, stacks);
+    sim.run(moves, |_, _| {})?;
+    Ok(sim)
 }
 
 #[cfg(test)]
@@ -96,11 +173,30 @@ move 1 from 1 to 2
 
     #[test]
     fn part1_example() {
-        assert_eq!(part1(INPUT), "CMZ");
+        assert_eq!(part1(INPUT).unwrap(), "CMZ");
     }
 
     #[test]
     fn part2_example() {
-        assert_eq!(part2(INPUT), "MCD");
+        assert_eq!(part2(INPUT).unwrap(), "MCD");
+    }
+
+    #[test]
+    fn invalid_move() {
+        let input = INPUT.replace("move 2 from 2 to 1", "move 2 from 0 to 1");
+        let err = part1(&input).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 8: stacks are numbered from 1 in `move 2 from 0 to 1`"
+        );
+
+        // Moves that can't be carried out point at the same lines.
+        let input = INPUT.replace("move 2 from 2 to 1", "move 2 from 4 to 1");
+        assert_eq!(part1(&input).unwrap_err().to_string(), "line 8: no stack 4");
+        let input = format!("{INPUT}\n\nmove 9 from 1 to 2");
+        assert_eq!(
+            part2(&input).unwrap_err().to_string(),
+            "line 12: cannot take 9 crates from stack 1, which has 1"
+        );
     }
 }
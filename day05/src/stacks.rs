@@ -0,0 +1,162 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{Error, Result};
+
+/// Stacks of crates, each listed bottom to top.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Stacks {
+    stacks: Vec<Vec<char>>,
+}
+
+impl Stacks {
+    pub fn len(&self) -> usize {
+        self.stacks.len()
+    }
+
+    pub fn get(&self, i: usize) -> Option<&[char]> {
+        self.stacks.get(i).map(Vec::as_slice)
+    }
+
+    /// Takes the top `count` crates off stack `i`.
+    pub fn lift(&mut self, i: usize, count: usize) -> Vec<char> {
+        let stack = &mut self.stacks[i];
+        stack.split_off(stack.len() - count)
+    }
+
+    pub fn place(&mut self, i: usize, crates: impl IntoIterator<Item = char>) {
+        self.stacks[i].extend(crates);
+    }
+
+    /// Crates on top of every stack, skipping empty ones.
+    pub fn tops(&self) -> String {
+        self.stacks.iter().filter_map(|s| s.last()).collect()
+    }
+}
+
+/// Parses the puzzle's drawing: rows of `[c]` cells four columns apart, any
+/// printable `c`, above a row numbering the stacks from 1.
+impl FromStr for Stacks {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let lines = s.lines().collect::<Vec<_>>();
+        let (labels, rows) = lines.split_last().ok_or("missing stack labels")?;
+
+        let mut n = 0;
+        let labels = labels.chars().collect::<Vec<_>>();
+        let mut col = 0;
+        while col < labels.len() {
+            if labels[col].is_whitespace() {
+                col += 1;
+                continue;
+            }
+            let len = labels[col..]
+                .iter()
+                .position(|c| c.is_whitespace())
+                .unwrap_or(labels.len() - col);
+            let label = String::from_iter(&labels[col..col + len]);
+            if label.parse::<usize>().ok() != Some(n + 1) || col / 4 != n || col % 4 == 3 {
+                return Err(format!("expected label {} in column {}", n + 1, n * 4 + 1).into());
+            }
+            n += 1;
+            col += len;
+        }
+
+        let mut stacks = vec![Vec::new(); n];
+        for (i, row) in rows.iter().enumerate().rev() {
+            let err = |msg: String| Error::from(format!("line {}: {msg}", i + 1));
+            let row = row.trim_end().chars().collect::<Vec<_>>();
+            for (k, cell) in row.chunks(4).enumerate() {
+                let c = match cell {
+                    [' ', ' ', ' ', ' '] | [' ', ' ', ' '] => continue,
+                    ['[', c, ']', ' '] | ['[', c, ']'] if !c.is_whitespace() && !c.is_control() => {
+                        *c
+                    }
+                    _ => return Err(err(format!("invalid cell `{}`", String::from_iter(cell)))),
+                };
+                let stack = stacks
+                    .get_mut(k)
+                    .ok_or_else(|| err(format!("crate `{c}` beyond the last stack")))?;
+                if stack.len() + i + 1 != rows.len() {
+                    return Err(err(format!("crate `{c}` in stack {} floats", k + 1)));
+                }
+                stack.push(c);
+            }
+        }
+
+        Ok(Stacks { stacks })
+    }
+}
+
+/// Draws the stacks the way they're parsed, without trailing whitespace.
+impl fmt::Display for Stacks {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let height = self.stacks.iter().map(Vec::len).max().unwrap_or(0);
+        for h in (0..height).rev() {
+            let row = self
+                .stacks
+                .iter()
+                .map(|s| s.get(h).map_or("   ".to_string(), |c| format!("[{c}]")))
+                .collect::<Vec<_>>()
+                .join(" ");
+            writeln!(f, "{}", row.trim_end())?;
+        }
+        let labels = (1..=self.stacks.len())
+            .map(|i| format!("{i:^3}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        write!(f, "{}", labels.trim_end())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DRAWING: &str = "    [D]\n[N] [C]\n[Z] [M] [P]\n 1   2   3";
+
+    #[test]
+    fn round_trip() {
+        let stacks = DRAWING.parse::<Stacks>().unwrap();
+        assert_eq!(
+            stacks,
+            Stacks {
+                stacks: vec![vec!['Z', 'N'], vec!['M', 'C', 'D'], vec!['P']]
+            }
+        );
+        assert_eq!(stacks.to_string(), DRAWING);
+
+        let wide = Stacks {
+            stacks: (0..12)
+                .map(|i| "aé#1".chars().cycle().take(i % 5).collect())
+                .collect(),
+        };
+        let drawing = wide.to_string();
+        assert!(drawing.ends_with(" 9  10  11  12"));
+        assert_eq!(drawing.parse::<Stacks>().unwrap(), wide);
+    }
+
+    #[test]
+    fn trailing_whitespace() {
+        let padded = "    [D]    \n[N] [C]\n[Z] [M] [P]   \n 1   2   3 \n";
+        assert_eq!(padded.parse::<Stacks>().unwrap().to_string(), DRAWING);
+        let empty = "[A]\n 1   2".parse::<Stacks>().unwrap();
+        assert_eq!(empty.get(1), Some(&[][..]));
+    }
+
+    #[test]
+    fn invalid() {
+        for drawing in [
+            "",
+            "[A]\n 1   3",
+            "[A]\n   1",
+            "[A] [B]\n 1",
+            "[A]\n   \n 1",
+            "[A [B]\n 1   2",
+            "[ ]\n 1",
+        ] {
+            assert!(drawing.parse::<Stacks>().is_err(), "{drawing:?}");
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use crate::crane::Move;
+use crate::stacks::Stacks;
+
+/// Running statistics of a rearrangement, per stack.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stats {
+    moves: usize,
+    /// Highest every stack has been.
+    pub max_height: Vec<usize>,
+    /// Crates taken off every stack.
+    pub taken: Vec<usize>,
+    /// Crates put on every stack.
+    pub received: Vec<usize>,
+    /// Move that brought each crate, counted from 1, with 0 for the crates
+    /// that were there from the start.
+    arrivals: Vec<Vec<usize>>,
+    /// Every move in effect, with the arrivals of the crates it lifted and
+    /// the highest its target stack had been before it.
+    history: Vec<(Move, Vec<usize>, usize)>,
+}
+
+impl Stats {
+    pub fn new(stacks: &Stacks) -> Self {
+        let n = stacks.len();
+        let heights = (0..n).map(|i| stacks.get(i).map_or(0, <[_]>::len));
+        Self {
+            moves: 0,
+            max_height: heights.clone().collect(),
+            taken: vec![0; n],
+            received: vec![0; n],
+            arrivals: heights.map(|h| vec![0; h]).collect(),
+            history: Vec::new(),
+        }
+    }
+
+    /// Number of moves recorded and not undone.
+    pub fn moves(&self) -> usize {
+        self.moves
+    }
+
+    pub fn record(&mut self, m: Move) {
+        self.moves += 1;
+        self.taken[m.from] += m.count;
+        self.received[m.to] += m.count;
+
+        let from = &mut self.arrivals[m.from];
+        let lifted = from.split_off(from.len() - m.count);
+        let to = &mut self.arrivals[m.to];
+        to.resize(to.len() + m.count, self.moves);
+        let max = self.max_height[m.to];
+        self.max_height[m.to] = max.max(to.len());
+        self.history.push((m, lifted, max));
+    }
+
+    /// Forgets the last move recorded, as if it had never happened.
+    pub fn undo(&mut self) -> Option<Move> {
+        let (m, lifted, max) = self.history.pop()?;
+        self.moves -= 1;
+        self.taken[m.from] -= m.count;
+        self.received[m.to] -= m.count;
+
+        let to = &mut self.arrivals[m.to];
+        to.truncate(to.len() - m.count);
+        self.arrivals[m.from].extend(lifted);
+        self.max_height[m.to] = max;
+        Some(m)
+    }
+
+    /// Move that put the crate now on top of every non-empty stack there,
+    /// with 0 for a crate that hasn't moved.
+    pub fn top_arrivals(&self) -> Vec<Option<usize>> {
+        self.arrivals.iter().map(|s| s.last().copied()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crane::{CrateMover9000, Simulator};
+
+    #[test]
+    fn example() {
+        let stacks = "    [D]\n[N] [C]\n[Z] [M] [P]\n 1   2   3".parse().unwrap();
+        let mut stats = Stats::new(&stacks);
+        let mut sim = Simulator::new(CrateMover9000, stacks);
+        let moves = [
+            "move 1 from 2 to 1",
+            "move 3 from 1 to 3",
+            "move 2 from 2 to 1",
+            "move 1 from 1 to 2",
+        ]
+        .map(|m| m.parse().unwrap());
+        let mut before_last = None;
+        sim.run((1..).zip(moves), |m, _| {
+            if stats.moves() == 3 {
+                before_last = Some(stats.clone());
+            }
+            stats.record(m);
+        })
+        .unwrap();
+
+        assert_eq!(stats.max_height, [3, 3, 4]);
+        assert_eq!(stats.taken, [4, 3, 0]);
+        assert_eq!(stats.received, [3, 1, 3]);
+        // `C` got to the first stack on the third move, `M` to the second
+        // one on the last, and `Z` on top of the third on the second.
+        assert_eq!(stats.top_arrivals(), [Some(3), Some(4), Some(2)]);
+
+        let last = stats.clone();
+        assert_eq!(stats.undo(), Some(moves[3]));
+        assert_eq!(Some(&stats), before_last.as_ref());
+        assert_eq!(stats.top_arrivals(), [Some(3), None, Some(2)]);
+        stats.record(moves[3]);
+        assert_eq!(stats, last);
+    }
+}
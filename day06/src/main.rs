@@ -1,9 +1,21 @@
+use std::env;
 use std::io::{self, Read};
 
+mod marker;
+mod packet;
+
+use marker::Detector;
+use packet::{Decoder, End, Framing};
+
 type Error = Box<dyn std::error::Error + Send + Sync>;
 type Result<T, E = Error> = std::result::Result<T, E>;
 
 fn main() -> Result<()> {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    if !args.is_empty() {
+        return scan(&args);
+    }
+
     let mut input = String::new();
     io::stdin().read_to_string(&mut input)?;
 
@@ -13,28 +25,71 @@ fn main() -> Result<()> {
     Ok(())
 }
 
+/// Streams stdin and prints where every marker of `--window <n>` distinct
+/// bytes ends, or only the first one with `--first`. `--decode` splits it
+/// into messages after such markers instead, each running until the next
+/// marker, for `--length <n>` bytes or up to a `--delimiter <byte>`.
+fn scan(args: &[String]) -> Result<()> {
+    let mut framing = Framing {
+        marker: 4,
+        ..Framing::default()
+    };
+    let mut first = false;
+    let mut decode = false;
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let mut value = || args.next().ok_or(format!("missing value for `{flag}`"));
+        match flag.as_str() {
+            "--window" => {
+                framing.marker = value()?.parse()?;
+                if framing.marker == 0 {
+                    return Err("window should be at least 1".into());
+                }
+            }
+            "--first" => first = true,
+            "--decode" => decode = true,
+            "--length" => framing.end = End::Length(value()?.parse()?),
+            "--delimiter" => match value()?.as_bytes() {
+                &[b] => framing.end = End::Delimiter(b),
+                _ => return Err("delimiter should be a single byte".into()),
+            },
+            _ => return Err(format!("unknown flag `{flag}`").into()),
+        }
+    }
+
+    if decode {
+        let mut data = Vec::new();
+        io::stdin().read_to_end(&mut data)?;
+        for m in Decoder::new(&data, framing) {
+            println!("{}\t{}", m.offset, String::from_utf8_lossy(m.data));
+        }
+        return Ok(());
+    }
+
+    for end in marker::markers(io::stdin().lock(), framing.marker) {
+        println!("{}", end?);
+        if first {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
 fn part1(input: &str) -> usize {
-    solve::<4>(input)
+    solve(input, 4)
 }
 
 fn part2(input: &str) -> usize {
-    solve::<14>(input)
+    solve(input, 14)
 }
 
-fn solve<const N: usize>(input: &str) -> usize {
+fn solve(input: &str, n: usize) -> usize {
+    let mut detector = Detector::new(n);
     input
-        .as_bytes()
-        .windows(N)
-        .enumerate()
-        .find(|(_, c)| {
-            c.iter()
-                .try_fold(0, |m, x| {
-                    let m1 = m | (1 << (x - b'a'));
-                    (m1 != m).then_some(m1)
-                })
-                .is_some()
-        })
-        .map(|(i, _)| i + N)
+        .bytes()
+        .find(|&b| detector.push(b))
+        .map(|_| detector.position())
         .unwrap_or_default()
 }
 
@@ -0,0 +1,150 @@
+use std::io::{self, BufReader, Bytes, Read};
+
+/// Sliding window over a byte stream that tells when its last `n` bytes are
+/// all different. Counts of every byte value in the window are kept up to
+/// date, along with how many values appear more than once, so each byte costs
+/// the same whatever the window size.
+#[derive(Debug, Clone)]
+pub struct Detector {
+    window: Vec<u8>,
+    counts: [usize; 256],
+    repeated: usize,
+    seen: usize,
+}
+
+impl Detector {
+    pub fn new(n: usize) -> Self {
+        assert!(n > 0, "window should hold at least one byte");
+        Self {
+            window: vec![0; n],
+            counts: [0; 256],
+            repeated: 0,
+            seen: 0,
+        }
+    }
+
+    /// Number of bytes pushed so far.
+    pub fn position(&self) -> usize {
+        self.seen
+    }
+
+    /// Slides the window over `b`, returning whether it's now a marker.
+    pub fn push(&mut self, b: u8) -> bool {
+        let n = self.window.len();
+        let slot = &mut self.window[self.seen % n];
+        if self.seen >= n {
+            let old = &mut self.counts[*slot as usize];
+            *old -= 1;
+            if *old == 1 {
+                self.repeated -= 1;
+            }
+        }
+        *slot = b;
+        let new = &mut self.counts[b as usize];
+        *new += 1;
+        if *new == 2 {
+            self.repeated += 1;
+        }
+        self.seen += 1;
+        self.seen >= n && self.repeated == 0
+    }
+}
+
+/// Positions right after every marker in a stream, read as it goes.
+pub struct Markers<R> {
+    bytes: Bytes<BufReader<R>>,
+    detector: Detector,
+}
+
+impl<R: Read> Iterator for Markers<R> {
+    type Item = io::Result<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for b in self.bytes.by_ref() {
+            match b {
+                Ok(b) if self.detector.push(b) => return Some(Ok(self.detector.position())),
+                Ok(_) => {}
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        None
+    }
+}
+
+/// Finds markers of `n` distinct bytes in `reader`, including ones that
+/// overlap.
+pub fn markers<R: Read>(reader: R, n: usize) -> Markers<R> {
+    Markers {
+        bytes: BufReader::new(reader).bytes(),
+        detector: Detector::new(n),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn brute_force(data: &[u8], n: usize) -> Vec<usize> {
+        data.windows(n)
+            .enumerate()
+            .filter(|(_, w)| (1..w.len()).all(|i| !w[..i].contains(&w[i])))
+            .map(|(i, _)| i + n)
+            .collect()
+    }
+
+    /// Hands out data a byte at a time, like a slow device would.
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match (self.0.split_first(), buf.first_mut()) {
+                (Some((&b, rest)), Some(out)) => {
+                    *out = b;
+                    self.0 = rest;
+                    Ok(1)
+                }
+                _ => Ok(0),
+            }
+        }
+    }
+
+    #[test]
+    fn all_markers() {
+        let found = markers("abcabd".as_bytes(), 3).collect::<io::Result<Vec<_>>>();
+        assert_eq!(found.unwrap(), [3, 4, 5, 6]);
+        let found = markers("aabba".as_bytes(), 2).collect::<io::Result<Vec<_>>>();
+        assert_eq!(found.unwrap(), [3, 5]);
+        assert_eq!(markers("ab".as_bytes(), 3).count(), 0);
+    }
+
+    #[test]
+    fn any_bytes() {
+        let data = [0x00, 0xff, 0x00, b'\n', 0x80, 0xff];
+        let found = markers(Trickle(&data), 4).collect::<io::Result<Vec<_>>>();
+        assert_eq!(found.unwrap(), [5, 6]);
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        // Every string of up to 8 bytes out of 3, and a long one cycling
+        // through a whole alphabet.
+        let short = (0..=8).flat_map(|len| {
+            (0..3usize.pow(len)).map(move |i| {
+                (0..len)
+                    .map(|k| (i / 3usize.pow(k) % 3) as u8)
+                    .collect::<Vec<_>>()
+            })
+        });
+        let long = (0..2000).map(|i| (i * 7 % 26) as u8).collect::<Vec<_>>();
+        for data in short.chain([long]) {
+            for n in [1, 2, 3, 4, 14] {
+                let found = markers(data.as_slice(), n).collect::<io::Result<Vec<_>>>();
+                assert_eq!(
+                    found.unwrap(),
+                    brute_force(&data, n),
+                    "{data:?}, window {n}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use crate::marker::Detector;
+
+/// Where a message ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum End {
+    /// Right before the next marker starts.
+    NextMarker,
+    /// After a fixed number of bytes.
+    Length(usize),
+    /// Right before a delimiter byte, which belongs to no message.
+    Delimiter(u8),
+}
+
+/// How a datastream is split into messages: each starts after a marker of
+/// `marker` distinct bytes and goes on until `end`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Framing {
+    pub marker: usize,
+    pub end: End,
+}
+
+impl Default for Framing {
+    fn default() -> Self {
+        Self {
+            marker: 14,
+            end: End::NextMarker,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Message<'a> {
+    /// Position of the first byte in the datastream.
+    pub offset: usize,
+    pub data: &'a [u8],
+}
+
+/// Messages of a recorded datastream, in order. Bytes that aren't preceded
+/// by a marker are skipped, and the last message may be cut short.
+#[derive(Debug, Clone)]
+pub struct Decoder<'a> {
+    data: &'a [u8],
+    pos: usize,
+    framing: Framing,
+}
+
+impl<'a> Decoder<'a> {
+    pub fn new(data: &'a [u8], framing: Framing) -> Self {
+        assert!(framing.marker > 0, "marker should be at least one byte");
+        Self {
+            data,
+            pos: 0,
+            framing,
+        }
+    }
+
+    /// Position right after the first marker from `from` on.
+    fn marker(&self, from: usize) -> Option<usize> {
+        let mut detector = Detector::new(self.framing.marker);
+        self.data[from..]
+            .iter()
+            .position(|&b| detector.push(b))
+            .map(|i| from + i + 1)
+    }
+}
+
+impl<'a> Iterator for Decoder<'a> {
+    type Item = Message<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = self.marker(self.pos)?;
+        let (end, next) = match self.framing.end {
+            End::NextMarker => match self.marker(start) {
+                Some(m) => (m - self.framing.marker, m - self.framing.marker),
+                None => (self.data.len(), self.data.len()),
+            },
+            End::Length(n) => {
+                let end = (start + n).min(self.data.len());
+                (end, end)
+            }
+            End::Delimiter(d) => match self.data[start..].iter().position(|&b| b == d) {
+                Some(i) => (start + i, start + i + 1),
+                None => (self.data.len(), self.data.len()),
+            },
+        };
+        self.pos = next;
+        Some(Message {
+            offset: start,
+            data: &self.data[start..end],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(data: &str, marker: usize, end: End) -> Vec<(usize, &str)> {
+        Decoder::new(data.as_bytes(), Framing { marker, end })
+            .map(|m| (m.offset, std::str::from_utf8(m.data).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn next_marker() {
+        assert_eq!(
+            decode("aaabcxxyyzzzdefggh", 3, End::NextMarker),
+            [(5, "xxyyzz"), (14, "fggh")]
+        );
+        // The puzzle's first example holds a single message.
+        let messages = decode("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 14, End::NextMarker);
+        assert_eq!(messages, [(19, "jfqwrcgsmlb")]);
+    }
+
+    #[test]
+    fn length() {
+        assert_eq!(
+            decode("abcHELLOqrsWORLDzz", 3, End::Length(5)),
+            [(3, "HELLO"), (11, "WORLD")]
+        );
+        assert_eq!(decode("abcHI", 3, End::Length(5)), [(3, "HI")]);
+    }
+
+    #[test]
+    fn delimiter() {
+        assert_eq!(
+            decode("xyzone;xyztwo;abc", 3, End::Delimiter(b';')),
+            [(3, "one"), (10, "two"), (17, "")]
+        );
+        assert!(decode("aaaa", 2, End::Delimiter(b';')).is_empty());
+    }
+}
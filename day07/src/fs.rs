@@ -0,0 +1,345 @@
+use std::collections::BTreeMap;
+use std::error;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FsErrorKind {
+	/// `cd` into a directory that hasn't been listed.
+	UnknownDir(String),
+	/// Command other than `cd <path>` and `ls`.
+	UnknownCommand(String),
+	/// Entry listed again as something else or with another size.
+	Conflict(String),
+	/// Listing line that's neither `dir <name>` nor `<size> <name>`.
+	InvalidEntry(String),
+	/// Listing without a preceding `ls`.
+	UnexpectedOutput(String),
+}
+
+/// Error in the terminal transcript, with a line number starting from 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FsError {
+	pub line: usize,
+	pub kind: FsErrorKind,
+}
+
+impl fmt::Display for FsError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "line {}: ", self.line)?;
+		match &self.kind {
+			FsErrorKind::UnknownDir(d) => write!(f, "unknown directory `{d}`"),
+			FsErrorKind::UnknownCommand(c) => write!(f, "unknown command `{c}`"),
+			FsErrorKind::Conflict(e) => write!(f, "conflicting listing of `{e}`"),
+			FsErrorKind::InvalidEntry(l) => write!(f, "invalid entry `{l}`"),
+			FsErrorKind::UnexpectedOutput(l) => write!(f, "unexpected output `{l}`"),
+		}
+	}
+}
+
+impl error::Error for FsError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Kind {
+	Dir(BTreeMap<String, usize>),
+	File(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Node {
+	name: String,
+	parent: usize,
+	kind: Kind,
+}
+
+/// Entry found in the file system, with its absolute path and total size.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+	pub path: String,
+	pub size: usize,
+	pub is_dir: bool,
+}
+
+/// In-memory file system tree. Nodes live in an arena with the root first,
+/// and every node points back to its parent, the root to itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fs {
+	nodes: Vec<Node>,
+}
+
+const ROOT: usize = 0;
+
+impl Fs {
+	/// Builds the tree from a terminal transcript of `cd` and `ls` commands.
+	/// `cd` takes relative or absolute paths, and listing a directory again
+	/// only adds what's new.
+	pub fn parse(transcript: &str) -> Result<Self, FsError> {
+		let mut fs = Fs {
+			nodes: vec![Node {
+				name: String::new(),
+				parent: ROOT,
+				kind: Kind::Dir(BTreeMap::new()),
+			}],
+		};
+		let mut cwd = ROOT;
+		let mut listing = false;
+
+		for (i, l) in transcript.lines().enumerate() {
+			let err = |kind| FsError { line: i + 1, kind };
+
+			if let Some(cmd) = l.strip_prefix("$ ") {
+				listing = false;
+				match cmd.split_whitespace().collect::<Vec<_>>()[..] {
+					["ls"] => listing = true,
+					["cd", path] => {
+						cwd = fs
+							.walk(cwd, path)
+							.filter(|&d| matches!(fs.nodes[d].kind, Kind::Dir(_)))
+							.ok_or_else(|| err(FsErrorKind::UnknownDir(path.to_string())))?;
+					},
+					_ => return Err(err(FsErrorKind::UnknownCommand(cmd.to_string()))),
+				}
+				continue;
+			}
+			if l.trim().is_empty() {
+				continue;
+			}
+			if !listing {
+				return Err(err(FsErrorKind::UnexpectedOutput(l.to_string())));
+			}
+
+			let (meta, name) = l
+				.split_once(' ')
+				.ok_or_else(|| err(FsErrorKind::InvalidEntry(l.to_string())))?;
+			let kind = match meta {
+				"dir" => Kind::Dir(BTreeMap::new()),
+				size => Kind::File(
+					size.parse()
+						.map_err(|_| err(FsErrorKind::InvalidEntry(l.to_string())))?,
+				),
+			};
+			match fs.child(cwd, name).map(|c| &fs.nodes[c].kind) {
+				None => fs.add(cwd, name, kind),
+				Some(Kind::Dir(_)) if matches!(kind, Kind::Dir(_)) => {},
+				Some(Kind::File(s)) if kind == Kind::File(*s) => {},
+				Some(_) => return Err(err(FsErrorKind::Conflict(fs.path_of(cwd, name)))),
+			}
+		}
+
+		Ok(fs)
+	}
+
+	fn child(&self, dir: usize, name: &str) -> Option<usize> {
+		match &self.nodes[dir].kind {
+			Kind::Dir(children) => children.get(name).copied(),
+			Kind::File(_) => None,
+		}
+	}
+
+	fn add(&mut self, dir: usize, name: &str, kind: Kind) {
+		let id = self.nodes.len();
+		self.nodes.push(Node {
+			name: name.to_string(),
+			parent: dir,
+			kind,
+		});
+		if let Kind::Dir(children) = &mut self.nodes[dir].kind {
+			children.insert(name.to_string(), id);
+		}
+	}
+
+	fn path(&self, mut id: usize) -> String {
+		let mut parts = Vec::new();
+		while id != ROOT {
+			parts.push(self.nodes[id].name.as_str());
+			id = self.nodes[id].parent;
+		}
+		parts.reverse();
+		format!("/{}", parts.join("/"))
+	}
+
+	fn path_of(&self, dir: usize, name: &str) -> String {
+		match self.path(dir).as_str() {
+			"/" => format!("/{name}"),
+			p => format!("{p}/{name}"),
+		}
+	}
+
+	/// Finds a node by a path, relative to `from` unless it's absolute.
+	fn walk(&self, from: usize, path: &str) -> Option<usize> {
+		let from = if path.starts_with('/') { ROOT } else { from };
+		path.split('/').try_fold(from, |id, part| match part {
+			"" | "." => Some(id),
+			".." => Some(self.nodes[id].parent),
+			name => self.child(id, name),
+		})
+	}
+
+	/// Total size of every node. Children always come after their parents in
+	/// the arena, so a single backwards pass adds them up.
+	fn sizes(&self) -> Vec<usize> {
+		let mut sizes = self
+			.nodes
+			.iter()
+			.map(|n| match n.kind {
+				Kind::File(s) => s,
+				Kind::Dir(_) => 0,
+			})
+			.collect::<Vec<_>>();
+		for id in (1..self.nodes.len()).rev() {
+			sizes[self.nodes[id].parent] += sizes[id];
+		}
+		sizes
+	}
+
+	/// Every file and directory, the root included.
+	pub fn entries(&self) -> Vec<Entry> {
+		let sizes = self.sizes();
+		self.nodes
+			.iter()
+			.enumerate()
+			.map(|(id, n)| Entry {
+				path: self.path(id),
+				size: sizes[id],
+				is_dir: matches!(n.kind, Kind::Dir(_)),
+			})
+			.collect()
+	}
+
+	pub fn dirs(&self) -> Vec<Entry> {
+		self.find(|e| e.is_dir)
+	}
+
+	pub fn find<P>(&self, pred: P) -> Vec<Entry>
+	where
+		P: Fn(&Entry) -> bool,
+	{
+		self.entries().into_iter().filter(|e| pred(e)).collect()
+	}
+
+	/// Total size of the file or directory at the absolute `path`.
+	pub fn du(&self, path: &str) -> Option<usize> {
+		self.walk(ROOT, path).map(|id| self.sizes()[id])
+	}
+
+	fn fmt_node(&self, f: &mut fmt::Formatter, id: usize, depth: usize) -> fmt::Result {
+		let n = &self.nodes[id];
+		let name = if id == ROOT { "/" } else { &n.name };
+		write!(f, "{:1$}- {name} ", "", depth * 2)?;
+		match &n.kind {
+			Kind::File(size) => writeln!(f, "(file, size={size})"),
+			Kind::Dir(children) => {
+				writeln!(f, "(dir)")?;
+				for &c in children.values() {
+					self.fmt_node(f, c, depth + 1)?;
+				}
+				Ok(())
+			},
+		}
+	}
+}
+
+/// Prints the tree the way the puzzle does.
+impl fmt::Display for Fs {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		self.fmt_node(f, ROOT, 0)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::tests::INPUT;
+
+	#[test]
+	fn tree() {
+		let fs = Fs::parse(INPUT).unwrap();
+		let tree = "\
+- / (dir)
+  - a (dir)
+    - e (dir)
+      - i (file, size=584)
+    - f (file, size=29116)
+    - g (file, size=2557)
+    - h.lst (file, size=62596)
+  - b.txt (file, size=14848514)
+  - c.dat (file, size=8504156)
+  - d (dir)
+    - d.ext (file, size=5626152)
+    - d.log (file, size=8033020)
+    - j (file, size=4060174)
+    - k (file, size=7214296)
+";
+		assert_eq!(fs.to_string(), tree);
+	}
+
+	#[test]
+	fn queries() {
+		let fs = Fs::parse(INPUT).unwrap();
+		assert_eq!(fs.du("/"), Some(48381165));
+		assert_eq!(fs.du("/a"), Some(94853));
+		assert_eq!(fs.du("/a/e/i"), Some(584));
+		assert_eq!(fs.du("/x"), None);
+
+		let found = fs.find(|e| e.path.ends_with(".log"));
+		assert_eq!(
+			found,
+			[Entry {
+				path: "/d/d.log".to_string(),
+				size: 8033020,
+				is_dir: false,
+			}]
+		);
+		let dirs = fs.dirs().into_iter().map(|e| e.path).collect::<Vec<_>>();
+		assert_eq!(dirs, ["/", "/a", "/d", "/a/e"]);
+	}
+
+	#[test]
+	fn paths() {
+		let fs = Fs::parse(
+			"$ ls\ndir a\n$ cd a\n$ ls\ndir b\n$ cd b/\n$ ls\ndir c\n\
+			$ cd /a/b/c\n$ ls\n10 x\n$ cd ../../.\n$ ls\n20 y\n\
+			$ cd /\n$ cd a/./b\n$ ls\n5 z\ndir c\n$ cd c\n$ ls\n10 x",
+		)
+		.unwrap();
+		assert_eq!(fs.du("/a/b/c"), Some(10));
+		assert_eq!(fs.du("/a/b"), Some(15));
+		assert_eq!(fs.du("/a"), Some(35));
+		assert_eq!(fs.du("/a/b/../y"), Some(20));
+		assert_eq!(fs.entries().len(), 7);
+	}
+
+	#[test]
+	fn validation() {
+		let err = Fs::parse("$ cd /\n$ ls\ndir a\n$ cd b").unwrap_err();
+		assert_eq!(
+			err,
+			FsError {
+				line: 4,
+				kind: FsErrorKind::UnknownDir("b".to_string()),
+			}
+		);
+
+		let err = Fs::parse("$ ls\n1 a\n$ ls\n2 a").unwrap_err();
+		assert_eq!(err.kind, FsErrorKind::Conflict("/a".to_string()));
+		let err = Fs::parse("$ ls\n1 a\n$ ls\ndir a").unwrap_err();
+		assert_eq!(err.kind, FsErrorKind::Conflict("/a".to_string()));
+		// Listing the same thing twice is fine.
+		assert!(Fs::parse("$ ls\n1 a\ndir b\n$ ls\n1 a\ndir b").is_ok());
+
+		let err = Fs::parse("$ ls\n1 a\n$ cd a").unwrap_err();
+		assert_eq!(err.kind, FsErrorKind::UnknownDir("a".to_string()));
+		let err = Fs::parse("$ ls\n$ rm -rf a").unwrap_err();
+		assert_eq!(
+			err,
+			FsError {
+				line: 2,
+				kind: FsErrorKind::UnknownCommand("rm -rf a".to_string()),
+			}
+		);
+
+		let err = Fs::parse("$ ls\nabc").unwrap_err();
+		assert_eq!(err.kind, FsErrorKind::InvalidEntry("abc".to_string()));
+		let err = Fs::parse("1 a").unwrap_err();
+		assert_eq!(err.kind, FsErrorKind::UnexpectedOutput("1 a".to_string()));
+	}
+}
@@ -1,6 +1,11 @@
+use std::env;
 use std::io::{self, Read};
-use std::collections::HashMap;
-use std::path::PathBuf;
+
+mod fs;
+mod plan;
+
+use fs::Fs;
+use plan::Planner;
 
 type Error = Box<dyn std::error::Error + Send + Sync>;
 type Result<T, E = Error> = std::result::Result<T, E>;
@@ -9,60 +14,88 @@ fn main() -> Result<()> {
 	let mut input = String::new();
 	io::stdin().read_to_string(&mut input)?;
 
-	println!("p1 = {}", part1(&input));
-	println!("p2 = {}", part2(&input));
+	let fs = Fs::parse(&input)?;
+	let args = env::args().skip(1).collect::<Vec<_>>();
+	if !args.is_empty() {
+		return query(&fs, &args);
+	}
+
+	println!("p1 = {}", part1(&fs));
+	println!("p2 = {}", part2(&fs));
+
+	Ok(())
+}
+
+/// Queries the file system: `tree` prints it, `du <path>` gives the total
+/// size of a file or directory and `find <name>` lists entries by name.
+/// `plan` picks directories to delete, see [`plan`].
+fn query(fs: &Fs, args: &[String]) -> Result<()> {
+	match args {
+		[cmd] if cmd == "tree" => print!("{fs}"),
+		[cmd, path] if cmd == "du" => {
+			let size = fs.du(path).ok_or(format!("no such file or directory `{path}`"))?;
+			println!("{size}\t{path}");
+		},
+		[cmd, name] if cmd == "find" => {
+			for e in fs.find(|e| e.path.rsplit('/').next() == Some(name.as_str())) {
+				let kind = if e.is_dir { "dir" } else { "file" };
+				println!("{}\t{kind}\t{}", e.size, e.path);
+			}
+		},
+		[cmd, args @ ..] if cmd == "plan" => plan(fs, args)?,
+		_ => return Err("expected `tree`, `du <path>`, `find <name>` or `plan`".into()),
+	}
 
 	Ok(())
 }
 
-fn part1(input: &str) -> usize {
-	traverse(input).into_values().filter(|&s| s <= 100000).sum()
+/// Plans deletions with `--capacity <bytes>` and `--required <bytes>`,
+/// defaulting to the puzzle's. `--set` allows several directories.
+fn plan(fs: &Fs, args: &[String]) -> Result<()> {
+	let mut planner = Planner::default();
+	let mut set = false;
+	let mut args = args.iter();
+	while let Some(flag) = args.next() {
+		let mut value = || args.next().ok_or(format!("missing value for `{flag}`"));
+		match flag.as_str() {
+			"--capacity" => planner.capacity = value()?.parse()?,
+			"--required" => planner.required = value()?.parse()?,
+			"--set" => set = true,
+			_ => return Err(format!("unknown flag `{flag}`").into()),
+		}
+	}
+
+	let plan = if set {
+		planner.minimal_set(fs)
+	} else {
+		planner.smallest(fs).map(|e| plan::Plan {
+			freed: e.size,
+			dirs: vec![e],
+		})
+	};
+	let plan = plan.ok_or("not enough space can be freed")?;
+	println!("needed: {}", planner.needed(fs));
+	for e in &plan.dirs {
+		println!("{}\t{}", e.size, e.path);
+	}
+	println!("freed: {}", plan.freed);
+
+	Ok(())
 }
 
-fn part2(input: &str) -> usize {
-	let sizes = traverse(input);
-	let total = sizes.get(&PathBuf::new()).copied().unwrap_or(0);
-	sizes
-		.into_values()
-		.filter(|s| 70000000 - (total - s) >= 30000000)
-		.min()
-		.unwrap_or(total)
+fn part1(fs: &Fs) -> usize {
+	fs.dirs().into_iter().map(|e| e.size).filter(|&s| s <= 100000).sum()
 }
 
-fn traverse(input: &str) -> HashMap<PathBuf, usize> {
-	input
-		.lines()
-		.try_fold(
-			(HashMap::new(), PathBuf::new()),
-			|(mut sizes, mut cwd), l| {
-				if let Some(dir) = l.strip_prefix("$ cd ") {
-					match dir {
-						"/"  => { cwd = PathBuf::new(); },
-						".." => { cwd.pop(); },
-						dir  => { cwd.push(dir); },
-					};
-				} else if l.starts_with(|c: char| c.is_ascii_digit()) {
-					let size = l.split_once(' ')?.0.parse::<usize>().ok()?;
-					let mut p = cwd.clone();
-					loop {
-						*sizes.entry(p.clone()).or_insert(0) += size;
-						if !p.pop() {
-							break;
-						}
-					}
-				}
-				Some((sizes, cwd))
-			},
-		)
-		.unwrap_or_default()
-		.0
+fn part2(fs: &Fs) -> usize {
+	Planner::default().smallest(fs).map_or(0, |e| e.size)
 }
 
 #[cfg(test)]
 mod tests {
 	use super::*;
 
-	const INPUT: &str = r#"$ cd /
+	pub const INPUT: &str = r#"$ cd /
 $ ls
 dir a
 14848514 b.txt
@@ -88,11 +121,11 @@ $ ls
 
 	#[test]
 	fn part1_example() {
-		assert_eq!(part1(INPUT), 95437);
+		assert_eq!(part1(&Fs::parse(INPUT).unwrap()), 95437);
 	}
 
 	#[test]
 	fn part2_example() {
-		assert_eq!(part2(INPUT), 24933642);
+		assert_eq!(part2(&Fs::parse(INPUT).unwrap()), 24933642);
 	}
 }
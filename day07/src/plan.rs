@@ -0,0 +1,267 @@
+use crate::fs::{Entry, Fs};
+
+/// Directories to delete and the space that frees.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Plan {
+	pub dirs: Vec<Entry>,
+	pub freed: usize,
+}
+
+/// Picks directories to delete so that at least `required` bytes of a
+/// `capacity`-sized disk end up free.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Planner {
+	pub capacity: usize,
+	pub required: usize,
+}
+
+impl Default for Planner {
+	fn default() -> Self {
+		Self {
+			capacity: 70000000,
+			required: 30000000,
+		}
+	}
+}
+
+impl Planner {
+	/// Space still missing once the current contents are accounted for.
+	pub fn needed(&self, fs: &Fs) -> usize {
+		let used = fs.du("/").unwrap_or(0);
+		self.required.saturating_sub(self.capacity.saturating_sub(used))
+	}
+
+	/// Smallest single directory that frees enough space.
+	pub fn smallest(&self, fs: &Fs) -> Option<Entry> {
+		let needed = self.needed(fs);
+		fs.dirs().into_iter().filter(|e| e.size >= needed).min_by_key(|e| e.size)
+	}
+
+	/// Set of directories that frees enough space while deleting as little as
+	/// possible.
+	///
+	/// Deleting a directory deletes everything inside it, so only directories
+	/// that aren't nested in each other are combined. With the directories in
+	/// depth-first order, each one is either skipped or taken along with its
+	/// whole subtree, and a bitset tracks the totals reachable from there on.
+	/// Going backwards, the only bitsets kept are the latest one and those
+	/// just past the subtrees of the current directory's ancestors. Each total
+	/// also remembers the directory that made it reachable, which is enough to
+	/// retrace it: what's left without that directory was reachable past its
+	/// subtree, from a later directory. Only directories smaller than what's
+	/// needed take part, so totals worth tracking stay below twice that.
+	pub fn minimal_set(&self, fs: &Fs) -> Option<Plan> {
+		let needed = self.needed(fs);
+		let single = self.smallest(fs)?;
+		let limit = single.size.min(needed * 2) + 1;
+
+		let mut dirs = fs.dirs().into_iter().filter(|e| e.size < needed).collect::<Vec<_>>();
+		dirs.sort_unstable_by(|a, b| a.path.split('/').cmp(b.path.split('/')));
+		let n = dirs.len();
+		let within = |d: &Entry, p: &Entry| d.path.starts_with(&format!("{}/", p.path));
+		let end = (0..n)
+			.map(|i| (i + 1..n).find(|&j| !within(&dirs[j], &dirs[i])).unwrap_or(n))
+			.collect::<Vec<_>>();
+
+		// Number of subtrees ending right before each position.
+		let mut ending = vec![0; n + 1];
+		for i in (0..n).filter(|&i| end[i] > i + 1) {
+			ending[end[i]] += 1;
+		}
+
+		// Directory that made each total reachable.
+		let mut first = vec![u32::MAX; limit];
+		let mut reach = vec![0u64; limit.div_ceil(64)];
+		reach[0] = 1;
+		// Totals reachable past the subtrees of the ancestors of the current
+		// directory, innermost last.
+		let mut past = Vec::<(Vec<u64>, usize)>::new();
+		for i in (0..n).rev() {
+			if ending[i + 1] > 0 {
+				past.push((reach.clone(), ending[i + 1]));
+			}
+			let mut r = reach.clone();
+			if end[i] > i + 1 {
+				let (p, uses) = past.last_mut().expect("subtrees should nest");
+				or_shifted(&mut r, p, dirs[i].size);
+				*uses -= 1;
+				if *uses == 0 {
+					past.pop();
+				}
+			} else {
+				or_shifted(&mut r, &reach, dirs[i].size);
+			}
+			mark(&mut first, &reach, &r, i);
+			reach = r;
+		}
+
+		let get = |s: usize| reach[s / 64] >> (s % 64) & 1 == 1;
+		let Some(mut left) = (needed..limit.min(single.size)).find(|&s| get(s)) else {
+			return Some(Plan {
+				freed: single.size,
+				dirs: vec![single],
+			});
+		};
+
+		let freed = left;
+		let mut picked = Vec::new();
+		while left > 0 {
+			let d = &dirs[first[left] as usize];
+			left -= d.size;
+			picked.push(d.clone());
+		}
+		picked.sort_unstable_by_key(|e| e.size);
+		Some(Plan { dirs: picked, freed })
+	}
+}
+
+/// Records `i` as the directory making the totals in `new` but not in `old`
+/// reachable.
+fn mark(first: &mut [u32], old: &[u64], new: &[u64], i: usize) {
+	for (w, (&o, &n)) in old.iter().zip(new).enumerate() {
+		let mut bits = n & !o;
+		while bits != 0 {
+			// Bits past the end of the last word are never looked at.
+			let Some(f) = first.get_mut(w * 64 + bits.trailing_zeros() as usize) else {
+				break;
+			};
+			*f = i as u32;
+			bits &= bits - 1;
+		}
+	}
+}
+
+/// Adds every total in `src` increased by `by` to `dst`.
+fn or_shifted(dst: &mut [u64], src: &[u64], by: usize) {
+	let (q, r) = (by / 64, by % 64);
+	for w in q..dst.len() {
+		dst[w] |= src[w - q] << r;
+		if r > 0 && w > q {
+			dst[w] |= src[w - q - 1] >> (64 - r);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::tests::INPUT;
+
+	fn paths(plan: &Plan) -> Vec<&str> {
+		plan.dirs.iter().map(|e| e.path.as_str()).collect()
+	}
+
+	#[test]
+	fn smallest() {
+		let fs = Fs::parse(INPUT).unwrap();
+		let planner = Planner::default();
+		assert_eq!(planner.needed(&fs), 8381165);
+		let e = planner.smallest(&fs).unwrap();
+		assert_eq!((e.path.as_str(), e.size), ("/d", 24933642));
+
+		let planner = Planner {
+			capacity: 48381165,
+			required: 500,
+		};
+		assert_eq!(planner.smallest(&fs).unwrap().path, "/a/e");
+		let planner = Planner {
+			capacity: 48381165,
+			required: 48381165,
+		};
+		assert_eq!(planner.smallest(&fs).unwrap().path, "/");
+		let planner = Planner {
+			capacity: 48381165,
+			required: 48381166,
+		};
+		assert_eq!(planner.smallest(&fs), None);
+	}
+
+	#[test]
+	fn minimal_set() {
+		let fs = Fs::parse(
+			"$ cd /\n$ ls\ndir a\ndir b\ndir c\n\
+			$ cd a\n$ ls\n60 x\ndir d\n$ cd d\n$ ls\n30 y\n\
+			$ cd /\n$ cd b\n$ ls\n45 z\n$ cd ..\n$ cd c\n$ ls\n100 w",
+		)
+		.unwrap();
+		// 235 used out of 300, so 35 more are needed for 100 free.
+		let planner = Planner {
+			capacity: 300,
+			required: 100,
+		};
+		assert_eq!(planner.smallest(&fs).unwrap().path, "/b");
+		let plan = planner.minimal_set(&fs).unwrap();
+		assert_eq!(paths(&plan), ["/b"]);
+		assert_eq!(plan.freed, 45);
+
+		// 75 needed: `/d` and `/b` beat `/a` on its own, which is 90.
+		let planner = Planner {
+			capacity: 300,
+			required: 140,
+		};
+		assert_eq!(planner.smallest(&fs).unwrap().path, "/a");
+		let plan = planner.minimal_set(&fs).unwrap();
+		assert_eq!(paths(&plan), ["/a/d", "/b"]);
+		assert_eq!(plan.freed, 75);
+
+		// 120 needed, which `/a` and `/a/d` would only add up to by counting
+		// `/a/d` twice.
+		let planner = Planner {
+			capacity: 300,
+			required: 185,
+		};
+		let plan = planner.minimal_set(&fs).unwrap();
+		assert_eq!(paths(&plan), ["/a/d", "/c"]);
+		assert_eq!(plan.freed, 130);
+
+		let planner = Planner {
+			capacity: 300,
+			required: 65,
+		};
+		assert_eq!(planner.minimal_set(&fs).unwrap(), Plan { dirs: vec![], freed: 0 });
+		let planner = Planner {
+			capacity: 300,
+			required: 301,
+		};
+		assert_eq!(planner.minimal_set(&fs), None);
+	}
+
+	#[test]
+	fn nested_subtrees() {
+		// `/a`, `/a/b` and `/a/b/c` all end where `/d` starts.
+		let fs = Fs::parse(
+			"$ cd /\n$ ls\ndir a\ndir d\n$ cd a\n$ ls\n7 x\ndir b\n\
+			$ cd b\n$ ls\n11 y\ndir c\ndir e\n$ cd c\n$ ls\n13 z\n\
+			$ cd ..\n$ cd e\n$ ls\n2 v\n$ cd /\n$ cd d\n$ ls\n5 w",
+		)
+		.unwrap();
+		let dirs = fs.dirs();
+		let nested = |a: &Entry, b: &Entry| a.path.starts_with(&format!("{}/", b.path));
+		for required in 1..=38 {
+			let planner = Planner { capacity: 38, required };
+			let needed = planner.needed(&fs);
+			// Every set of directories not nested in each other.
+			let best = (0..1u32 << dirs.len())
+				.map(|m| (0..dirs.len()).filter(|&i| m >> i & 1 == 1).collect::<Vec<_>>())
+				.filter(|s| {
+					s.iter().all(|&i| s.iter().all(|&j| !nested(&dirs[i], &dirs[j])))
+				})
+				.map(|s| s.iter().map(|&i| dirs[i].size).sum::<usize>())
+				.filter(|&t| t >= needed)
+				.min();
+			let plan = planner.minimal_set(&fs);
+			assert_eq!(plan.as_ref().map(|p| p.freed), best, "{required}");
+			if let Some(plan) = plan {
+				assert_eq!(plan.dirs.iter().map(|e| e.size).sum::<usize>(), plan.freed);
+			}
+		}
+	}
+
+	#[test]
+	fn matches_smallest() {
+		let fs = Fs::parse(INPUT).unwrap();
+		let plan = Planner::default().minimal_set(&fs).unwrap();
+		assert_eq!(paths(&plan), ["/d"]);
+		assert_eq!(plan.freed, 24933642);
+	}
+}
@@ -1,5 +1,11 @@
-use std::collections::HashSet;
-use std::io::{self, Read};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::env;
+use std::io::{self, Read, Write};
+
+mod render;
+
+use render::Layer;
 
 type Error = Box<dyn std::error::Error + Send + Sync>;
 type Result<T, E = Error> = std::result::Result<T, E>;
@@ -9,27 +15,79 @@ fn main() -> Result<()> {
 	io::stdin().read_to_string(&mut input)?;
 
 	let ctx = Context::new(&input);
+	let args = env::args().skip(1).collect::<Vec<_>>();
+	if !args.is_empty() {
+		return inspect(&ctx, &args);
+	}
+
 	println!("p1 = {}", part1(&ctx));
 	println!("p2 = {}", part2(&ctx));
 
 	Ok(())
 }
 
-fn part1(ctx: &Context) -> usize {
-	let w = ctx.w;
-	let h = ctx.h;
+/// Reports details of the forest: `--tree <row>,<col>` for a single tree,
+/// `--top <k>` for the most scenic trees and `--histogram` for the number of
+/// trees with each scenic score. `--ansi <layer>` and `--ppm <layer>` draw
+/// either `visible` trees or a `scenic` heatmap instead.
+fn inspect(ctx: &Context, args: &[String]) -> Result<()> {
+	let v = Visibility::new(ctx);
+	let mut args = args.iter();
+	while let Some(flag) = args.next() {
+		let mut value = || args.next().ok_or(format!("missing value for `{flag}`"));
+		match flag.as_str() {
+			"--tree" => {
+				let (r, c) = value()?.split_once(',').ok_or("expected `<row>,<col>`")?;
+				let (r, c) = (r.parse()?, c.parse()?);
+				if r >= ctx.h || c >= ctx.w {
+					return Err(format!("no tree at {r},{c}").into());
+				}
+				let t = v.tree(r, c);
+				println!("tree at {r},{c}: height {}, score {}", t.height, t.score());
+				for d in Dir::ALL {
+					let seen = if t.visible_from(d) { "visible" } else { "hidden" };
+					println!("  {d:?}: {seen}, sees {}", t.distance(d));
+				}
+			},
+			"--top" => {
+				for ((r, c), t) in v.top(value()?.parse()?) {
+					println!("{r},{c}: height {}, score {}", t.height, t.score());
+				}
+			},
+			"--histogram" => {
+				for (score, n) in v.histogram() {
+					println!("{score}: {n}");
+				}
+			},
+			"--ansi" => {
+				print!("{}", render::to_ansi(&v, value()?.parse::<Layer>()?));
+				return Ok(());
+			},
+			"--ppm" => {
+				io::stdout().write_all(&render::to_ppm(&v, value()?.parse::<Layer>()?, 4))?;
+				return Ok(());
+			},
+			_ => return Err(format!("unknown flag `{flag}`").into()),
+		}
+	}
+	println!("visible: {}", v.visible_count());
 
-	let lr = (1..h - 1).flat_map(|r| ctx.step(w * r, (1..w - 1).map(move |c| (r, c))));
-	let rl = (1..h - 1).flat_map(|r| ctx.step(w * r + w - 1, (1..w - 1).map(move |c| (r, c)).rev()));
-	let tb = (1..w - 1).flat_map(|c| ctx.step(c, (1..h - 1).map(move |r| (r, c))));
-	let bt = (1..w - 1).flat_map(|c| ctx.step(w * (h - 1) + c, (1..h - 1).map(move |r| (r, c)).rev()));
+	Ok(())
+}
 
-	HashSet::<(usize, usize)>::from_iter(lr.chain(rl).chain(tb).chain(bt)).len() + w * h - (w - 2) * (h - 2)
+fn part1(ctx: &Context) -> usize {
+	let [l, r, u, d] = sweeps(ctx);
+	l.union(&r).union(&u).union(&d).count()
 }
 
 fn part2(ctx: &Context) -> usize {
+	Visibility::new(ctx).best().map_or(0, |(_, t)| t.score())
+}
+
+/// Viewing distances from each tree towards the left, right, top and bottom.
+fn distances(ctx: &Context) -> Vec<[usize; 4]> {
 	let mut stack: Vec<usize> = Vec::new();
-	let mut score = Vec::from_iter(std::iter::repeat([0; 4]).take(ctx.map.len()));
+	let mut score = vec![[0; 4]; ctx.map.len()];
 
 	let m = &ctx.map;
 	let w = ctx.w;
@@ -45,12 +103,12 @@ fn part2(ctx: &Context) -> usize {
 				}
 
 				stack.pop();
-				score[w * r + x][0] = c - x;
+				score[w * r + x][1] = c - x;
 			}
 			stack.push(c);
 		}
 		for x in stack.drain(..) {
-			score[w * r + x][0] = w - x - 1;
+			score[w * r + x][1] = w - x - 1;
 		}
 
 		// right to left
@@ -62,12 +120,12 @@ fn part2(ctx: &Context) -> usize {
 				}
 
 				stack.pop();
-				score[w * r + x][1] = x - c;
+				score[w * r + x][0] = x - c;
 			}
 			stack.push(c);
 		}
 		for x in stack.drain(..) {
-			score[w * r + x][1] = x;
+			score[w * r + x][0] = x;
 		}
 	}
 	for c in 0..w {
@@ -80,12 +138,12 @@ fn part2(ctx: &Context) -> usize {
 				}
 
 				stack.pop();
-				score[w * x + c][2] = r - x;
+				score[w * x + c][3] = r - x;
 			}
 			stack.push(r);
 		}
 		for x in stack.drain(..) {
-			score[w * x + c][2] = w - x - 1;
+			score[w * x + c][3] = h - x - 1;
 		}
 		// bottom to top
 		for r in (0..h).rev() {
@@ -96,20 +154,172 @@ fn part2(ctx: &Context) -> usize {
 				}
 
 				stack.pop();
-				score[w * x + c][3] = x - r;
+				score[w * x + c][2] = x - r;
 			}
 			stack.push(r);
 		}
 		for x in stack.drain(..) {
-			score[w * x + c][3] = x;
+			score[w * x + c][2] = x;
 		}
 	}
 
 	score
-		.into_iter()
-		.map(|[l, r, d, u]| l * r * d * u)
-		.max()
-		.unwrap_or(0)
+}
+
+/// Trees visible from the left, right, top and bottom edges, found by walking
+/// every line away from each edge once.
+fn sweeps(ctx: &Context) -> [Bitset; 4] {
+	let (w, h, m) = (ctx.w, ctx.h, &ctx.map);
+	let sweep = |lines: usize, len: usize, at: &dyn Fn(usize, usize) -> usize| {
+		let mut visible = Bitset::new(m.len());
+		for l in 0..lines {
+			let mut hi = None;
+			for i in 0..len {
+				let t = at(l, i);
+				if hi.is_none_or(|hi| m[t] > hi) {
+					visible.set(t);
+					hi = Some(m[t]);
+				}
+			}
+		}
+		visible
+	};
+
+	[
+		sweep(h, w, &|r, c| w * r + c),
+		sweep(h, w, &|r, c| w * r + w - 1 - c),
+		sweep(w, h, &|c, r| w * r + c),
+		sweep(w, h, &|c, r| w * (h - 1 - r) + c),
+	]
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Bitset {
+	words: Vec<u64>,
+}
+
+impl Bitset {
+	fn new(len: usize) -> Self {
+		Self {
+			words: vec![0; len.div_ceil(64)],
+		}
+	}
+
+	fn set(&mut self, i: usize) {
+		self.words[i / 64] |= 1 << (i % 64);
+	}
+
+	fn get(&self, i: usize) -> bool {
+		self.words[i / 64] & 1 << (i % 64) != 0
+	}
+
+	fn union(&self, other: &Bitset) -> Bitset {
+		let words = self.words.iter().zip(&other.words).map(|(a, b)| a | b).collect();
+		Bitset { words }
+	}
+
+	fn count(&self) -> usize {
+		self.words.iter().map(|w| w.count_ones() as usize).sum()
+	}
+}
+
+/// Direction from a tree towards an edge, indexing per-direction details.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Dir {
+	Left,
+	Right,
+	Up,
+	Down,
+}
+
+impl Dir {
+	const ALL: [Dir; 4] = [Dir::Left, Dir::Right, Dir::Up, Dir::Down];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Tree {
+	height: u8,
+	/// Whether the tree can be seen from outside the forest on each side.
+	visible: [bool; 4],
+	/// How many trees can be seen from the tree looking each way.
+	distance: [usize; 4],
+}
+
+impl Tree {
+	fn is_visible(&self) -> bool {
+		self.visible.contains(&true)
+	}
+
+	fn visible_from(&self, d: Dir) -> bool {
+		self.visible[d as usize]
+	}
+
+	fn distance(&self, d: Dir) -> usize {
+		self.distance[d as usize]
+	}
+
+	fn score(&self) -> usize {
+		self.distance.iter().product()
+	}
+}
+
+/// Per-tree visibility details of the whole forest.
+struct Visibility {
+	w: usize,
+	trees: Vec<Tree>,
+}
+
+impl Visibility {
+	fn new(ctx: &Context) -> Self {
+		let sweeps = sweeps(ctx);
+		let trees = ctx
+			.map
+			.iter()
+			.zip(distances(ctx))
+			.enumerate()
+			.map(|(i, (&height, distance))| Tree {
+				height,
+				visible: Dir::ALL.map(|d| sweeps[d as usize].get(i)),
+				distance,
+			})
+			.collect();
+
+		Self { w: ctx.w, trees }
+	}
+
+	fn tree(&self, r: usize, c: usize) -> &Tree {
+		&self.trees[self.w * r + c]
+	}
+
+	fn trees(&self) -> impl Iterator<Item = ((usize, usize), &Tree)> + '_ {
+		self.trees.iter().enumerate().map(|(i, t)| ((i / self.w, i % self.w), t))
+	}
+
+	fn visible_count(&self) -> usize {
+		self.trees.iter().filter(|t| t.is_visible()).count()
+	}
+
+	/// Tree with the highest scenic score, the first one on ties.
+	fn best(&self) -> Option<((usize, usize), &Tree)> {
+		self.top(1).into_iter().next()
+	}
+
+	/// `k` most scenic trees, best first.
+	fn top(&self, k: usize) -> Vec<((usize, usize), &Tree)> {
+		let mut trees = self.trees().collect::<Vec<_>>();
+		trees.sort_by_key(|(_, t)| Reverse(t.score()));
+		trees.truncate(k);
+		trees
+	}
+
+	/// Number of trees with each scenic score.
+	fn histogram(&self) -> BTreeMap<usize, usize> {
+		let mut histogram = BTreeMap::new();
+		for t in &self.trees {
+			*histogram.entry(t.score()).or_default() += 1;
+		}
+		histogram
+	}
 }
 
 struct Context {
@@ -134,22 +344,6 @@ impl Context {
 
 		Self { map, w, h }
 	}
-
-	fn step<'s>(
-		&'s self,
-		hi_index: usize,
-		indices: impl Iterator<Item = (usize, usize)> + 's,
-	) -> impl Iterator<Item = (usize, usize)> + 's
-	{
-		indices
-			.scan(self.map[hi_index], move |hi, (r, c)| {
-				let t = self.map[self.w * r + c];
-				let result = Some((t > *hi).then_some((r, c)));
-				*hi = (*hi).max(t);
-				result
-			})
-			.flatten()
-	}
 }
 
 #[cfg(test)]
@@ -171,4 +365,88 @@ mod tests {
 	fn part2_example() {
 		assert_eq!(part2(&Context::new(INPUT)), 8);
 	}
+
+	#[test]
+	fn visibility() {
+		let v = Visibility::new(&Context::new(INPUT));
+		assert_eq!(v.visible_count(), 21);
+
+		// The top-left 5 is visible from the left and top only.
+		let t = v.tree(1, 1);
+		assert_eq!(t.visible, [true, false, true, false]);
+		assert_eq!(t.distance, [1, 1, 1, 1]);
+
+		let t = v.tree(3, 2);
+		assert_eq!(t.height, 5);
+		assert_eq!(t.visible, [true, false, false, true]);
+		assert_eq!(t.distance, [2, 2, 2, 1]);
+		assert!(!v.tree(2, 2).is_visible());
+
+		assert_eq!(v.best().map(|(p, t)| (p, t.score())), Some(((3, 2), 8)));
+		let top = v.top(2).into_iter().map(|(p, t)| (p, t.score())).collect::<Vec<_>>();
+		assert_eq!(top, [((3, 2), 8), ((2, 1), 6)]);
+
+		let histogram = v.histogram();
+		assert_eq!(histogram[&0], 16);
+		assert_eq!(histogram.values().sum::<usize>(), 25);
+	}
+
+	/// Looks from every tree in every direction, one tree at a time.
+	fn brute_force(ctx: &Context) -> Vec<([bool; 4], [usize; 4])> {
+		let (w, h) = (ctx.w as isize, ctx.h as isize);
+		let at = |r: isize, c: isize| ctx.map[(w * r + c) as usize];
+		let mut trees = Vec::new();
+		for r in 0..h {
+			for c in 0..w {
+				let dirs = [(0, -1), (0, 1), (-1, 0), (1, 0)].map(|(dr, dc)| {
+					let (mut y, mut x) = (r + dr, c + dc);
+					let mut seen = 0;
+					while (0..h).contains(&y) && (0..w).contains(&x) {
+						seen += 1;
+						if at(y, x) >= at(r, c) {
+							return (false, seen);
+						}
+						(y, x) = (y + dr, x + dc);
+					}
+					(true, seen)
+				});
+				trees.push((dirs.map(|d| d.0), dirs.map(|d| d.1)));
+			}
+		}
+		trees
+	}
+
+	#[test]
+	fn matches_brute_force() {
+		// Every grid of up to 9 trees with 3 heights, and every 4x3 and 3x4
+		// one with 2, few heights making for plenty of ties. From 3x3 on,
+		// some trees have others on all four sides.
+		let small = (1..=9).flat_map(|w| (1..=9 / w).map(move |h| (w, h, 3usize)));
+		let large = [(4, 3, 2), (3, 4, 2)];
+		for (w, h, heights) in small.chain(large) {
+			for i in 0..heights.pow((w * h) as u32) {
+				let trees = (0..w * h)
+					.map(|k| (b'0' + (i / heights.pow(k as u32) % heights) as u8) as char)
+					.collect::<Vec<_>>();
+				let input = trees
+					.chunks(w)
+					.map(|row| row.iter().collect::<String>())
+					.collect::<Vec<_>>()
+					.join("\n");
+
+				let ctx = Context::new(&input);
+				let expected = brute_force(&ctx);
+				let v = Visibility::new(&ctx);
+				for (t, (visible, distance)) in v.trees.iter().zip(&expected) {
+					assert_eq!(t.visible, *visible, "{input}");
+					assert_eq!(t.distance, *distance, "{input}");
+				}
+
+				let visible = expected.iter().filter(|(v, _)| v.contains(&true)).count();
+				assert_eq!(part1(&ctx), visible, "{input}");
+				let score = expected.iter().map(|(_, d)| d.iter().product()).max();
+				assert_eq!(part2(&ctx), score.unwrap_or(0), "{input}");
+			}
+		}
+	}
 }
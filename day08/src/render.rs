@@ -0,0 +1,141 @@
+use std::fmt::Write;
+use std::str::FromStr;
+
+use crate::{Error, Result, Visibility};
+
+/// What the colours of the forest show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layer {
+	/// Trees visible from outside stand out, the rest are shaded by height.
+	Visible,
+	/// Scenic scores as a heatmap.
+	Scenic,
+}
+
+impl FromStr for Layer {
+	type Err = Error;
+
+	fn from_str(s: &str) -> Result<Self> {
+		match s {
+			"visible" => Ok(Layer::Visible),
+			"scenic" => Ok(Layer::Scenic),
+			_ => Err(format!("unknown layer `{s}`, expected `visible` or `scenic`").into()),
+		}
+	}
+}
+
+type Rgb = [u8; 3];
+
+fn colors(v: &Visibility, layer: Layer) -> Vec<Rgb> {
+	match layer {
+		Layer::Visible => v
+			.trees
+			.iter()
+			.map(|t| {
+				let shade = 0x20 + t.height * 0x0c;
+				if t.is_visible() {
+					[0x20, shade + 0x40, 0x20]
+				} else {
+					[shade, shade, shade]
+				}
+			})
+			.collect(),
+		Layer::Scenic => {
+			// Scores span orders of magnitude, so the scale is logarithmic.
+			let max = v.trees.iter().map(|t| t.score()).max().unwrap_or(0);
+			let norm = ((max + 1) as f64).ln().max(f64::EPSILON);
+			v.trees
+				.iter()
+				.map(|t| heat(((t.score() + 1) as f64).ln() / norm))
+				.collect()
+		},
+	}
+}
+
+/// Goes from black through blue and red to yellow as `t` goes from 0 to 1.
+fn heat(t: f64) -> Rgb {
+	let stops: [Rgb; 4] = [
+		[0x00, 0x00, 0x00],
+		[0x20, 0x30, 0xc0],
+		[0xe0, 0x20, 0x20],
+		[0xff, 0xe0, 0x40],
+	];
+	let t = t.clamp(0.0, 1.0) * (stops.len() - 1) as f64;
+	let i = (t as usize).min(stops.len() - 2);
+	let f = t - i as f64;
+	let (a, b) = (stops[i], stops[i + 1]);
+	[0, 1, 2].map(|c| (a[c] as f64 + (b[c] as f64 - a[c] as f64) * f).round() as u8)
+}
+
+/// Renders the forest as tree heights on coloured backgrounds, using ANSI
+/// true colour escapes.
+pub fn to_ansi(v: &Visibility, layer: Layer) -> String {
+	let colors = colors(v, layer);
+	let mut out = String::new();
+	for (row, colors) in v.trees.chunks(v.w.max(1)).zip(colors.chunks(v.w.max(1))) {
+		for (t, [r, g, b]) in row.iter().zip(colors) {
+			// Light digits on dark backgrounds and the other way around.
+			let luma = (*r as u32 * 3 + *g as u32 * 6 + *b as u32) / 10;
+			let fg = if luma < 0x80 { 0xff } else { 0x00 };
+			let _ = write!(
+				out,
+				"\x1b[48;2;{r};{g};{b}m\x1b[38;2;{fg};{fg};{fg}m{}",
+				t.height
+			);
+		}
+		out.push_str("\x1b[0m\n");
+	}
+	out
+}
+
+/// Renders the forest as a binary PPM, each tree being a `scale`-sized square.
+pub fn to_ppm(v: &Visibility, layer: Layer, scale: usize) -> Vec<u8> {
+	let colors = colors(v, layer);
+	let (w, h) = (v.w, v.trees.len() / v.w.max(1));
+	let mut out = format!("P6\n{} {}\n255\n", w * scale, h * scale).into_bytes();
+	for y in 0..h * scale {
+		for x in 0..w * scale {
+			out.extend(colors[(y / scale) * w + x / scale]);
+		}
+	}
+	out
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::Context;
+
+	const INPUT: &str = "393\n959\n393";
+
+	#[test]
+	fn ansi() {
+		let v = Visibility::new(&Context::new(INPUT));
+		let text = to_ansi(&v, Layer::Visible);
+		assert_eq!(text.lines().count(), 3);
+		// Only the center tree is hidden, and it's drawn in gray.
+		let shade = 0x20 + 5 * 0x0c;
+		let hidden = format!("\x1b[48;2;{shade};{shade};{shade}m\x1b[38;2;255;255;255m5");
+		assert_eq!(text.matches(&hidden).count(), 1);
+		assert_eq!(text.matches("m\x1b[38;2;").count(), 9);
+		assert!(text.lines().all(|l| l.ends_with("\x1b[0m")));
+	}
+
+	#[test]
+	fn ppm() {
+		let v = Visibility::new(&Context::new(INPUT));
+		let ppm = to_ppm(&v, Layer::Scenic, 2);
+		let header = b"P6\n6 6\n255\n";
+		assert!(ppm.starts_with(header));
+		assert_eq!(ppm.len(), header.len() + 6 * 6 * 3);
+		// Edge trees score nothing.
+		assert_eq!(ppm[header.len()..header.len() + 3], [0, 0, 0]);
+	}
+
+	#[test]
+	fn heatmap() {
+		assert_eq!(heat(0.0), [0x00, 0x00, 0x00]);
+		assert_eq!(heat(1.0), [0xff, 0xe0, 0x40]);
+		assert_eq!(heat(0.5), [0x80, 0x28, 0x70]);
+	}
+}
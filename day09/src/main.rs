@@ -1,5 +1,15 @@
-use std::collections::HashSet;
+use std::env;
+use std::fs;
 use std::io::{self, Read};
+use std::path::PathBuf;
+
+mod render;
+mod rope;
+mod segments;
+
+use render::{Animation, Every};
+use rope::{Dims, Move, Rope};
+use segments::SegmentRope;
 
 type Error = Box<dyn std::error::Error + Send + Sync>;
 type Result<T, E = Error> = std::result::Result<T, E>;
@@ -8,81 +18,123 @@ fn main() -> Result<()> {
 	let mut input = String::new();
 	io::stdin().read_to_string(&mut input)?;
 
-	println!("p1 = {}", part1(&input));
-	println!("p2 = {}", part2(&input));
+	let args = env::args().skip(1).collect::<Vec<_>>();
+	if !args.is_empty() {
+		return custom(&input, &args);
+	}
+
+	println!("p1 = {}", part1(&input)?);
+	println!("p2 = {}", part2(&input)?);
 
 	Ok(())
 }
 
-fn part1(input: &str) -> usize {
-	solve::<2>(input)
-}
+/// Simulates a rope configured with `--knots <n>` and `--3d` flags,
+/// reporting how many positions each knot has visited and where it ended up,
+/// and the tail's visited map with `--map`. With `--segments`, only the counts
+/// are reported, but moves of any length are handled quickly.
+///
+/// With `--render <text|ppm>`, draws a frame for every move, or every step
+/// with `--every step`, instead. Text frames are printed, while images are
+/// written into the `--out <dir>` directory, `--scale <n>` pixels per
+/// position.
+fn custom(input: &str, args: &[String]) -> Result<()> {
+	let mut knots = 10;
+	let mut dims = Dims::Two;
+	let mut map = false;
+	let mut segments = false;
+	let mut format = None;
+	let mut every = Every::Move;
+	let mut out = PathBuf::from(".");
+	let mut scale = 4;
+	let mut args = args.iter();
+	while let Some(flag) = args.next() {
+		let mut value = || args.next().ok_or(format!("missing value for `{flag}`"));
+		match flag.as_str() {
+			"--knots" => knots = value()?.parse()?,
+			"--3d" => dims = Dims::Three,
+			"--map" => map = true,
+			"--segments" => segments = true,
+			"--render" => format = Some(value()?.as_str()),
+			"--every" => {
+				every = match value()?.as_str() {
+					"step" => Every::Step,
+					"move" => Every::Move,
+					e => return Err(format!("unknown frame interval `{e}`").into()),
+				};
+			},
+			"--out" => out = value()?.into(),
+			"--scale" => scale = value()?.parse()?,
+			_ => return Err(format!("unknown flag `{flag}`").into()),
+		}
+	}
+	if knots == 0 {
+		return Err("rope should have at least one knot".into());
+	}
 
-fn part2(input: &str) -> usize {
-	solve::<10>(input)
-}
+	let mut rope = Rope::new(knots, dims);
+	let moves = input.lines().map(str::parse).collect::<Result<Vec<Move>>>()?;
+	match format {
+		Some("text") => {
+			// Frames are printed as they're drawn, there may be a lot of them.
+			let animation = Animation::record(rope, &moves, every)?;
+			for frame in animation.to_text() {
+				println!("{frame}");
+			}
+			return Ok(());
+		},
+		Some("ppm") => {
+			fs::create_dir_all(&out)?;
+			let animation = Animation::record(rope, &moves, every)?;
+			for (i, frame) in animation.to_ppm(scale).enumerate() {
+				fs::write(out.join(format!("frame{i:05}.ppm")), frame)?;
+			}
+			return Ok(());
+		},
+		Some(f) => return Err(format!("unknown format `{f}`").into()),
+		None => {},
+	}
 
-fn solve<const N: usize>(input: &str) -> usize {
-	input
-		.lines()
-		.filter_map(try_parse_move)
-		.fold(
-			(HashSet::<Pos>::new(), [(0, 0); N]),
-			|(mut visited, mut knots), (dir, n)| {
-				for _ in 0..n {
-					knots[0] = next(knots[0], dir);
-					for k in 1..N {
-						knots[k] = follow(knots[k - 1], knots[k]);
-					}
-					visited.insert(knots[N - 1]);
-				}
-
-				(visited, knots)
-			})
-		.0
-		.len()
-}
+	if segments {
+		let mut rope = SegmentRope::new(knots, dims);
+		for m in moves {
+			rope.apply(m)?;
+		}
+		for (k, v) in rope.visited().iter().enumerate() {
+			println!("{k}: visited {v}");
+		}
+		return Ok(());
+	}
+
+	for m in moves {
+		rope.apply(m)?;
+	}
+	for (k, (v, p)) in rope.visited().iter().zip(rope.knots()).enumerate() {
+		println!("{k}: visited {}, at {p:?}", v.len());
+	}
+	if map {
+		print!("{}", render::visited_map(rope.tail_visited()));
+	}
 
-fn try_parse_move(input: &str) -> Option<(u8, usize)> {
-	input
-		.split_once(' ')
-		.and_then(|(dir, n)| Some((dir.bytes().next()?, n.parse::<usize>().ok()?)))
+	Ok(())
 }
 
-type Pos = (isize, isize);
-
-fn next((x, y): Pos, dir: u8) -> Pos {
-	let (dx, dy) = match dir {
-		b'R' => ( 1,  0),
-		b'L' => (-1,  0),
-		b'U' => ( 0, -1),
-		b'D' => ( 0,  1),
-		_ => (0, 0),
-	};
-	(x + dx, y + dy)
+fn part1(input: &str) -> Result<usize> {
+	solve(input, 2)
 }
 
-fn follow((hx, hy): Pos, (tx, ty): Pos) -> Pos {
-	let dx = hx - tx;
-	let dy = hy - ty;
-
-	if dx.abs() <= 1 && dy.abs() <= 1 {
-		(tx, ty)
-	} else if dx.abs() > dy.abs() {
-		(hx - signum(dx), hy)
-	} else if dx.abs() < dy.abs() {
-		(hx, hy - signum(dy))
-	} else {
-		(hx - signum(dx), hy - signum(dy))
-	}
+fn part2(input: &str) -> Result<usize> {
+	solve(input, 10)
 }
 
-fn signum(x: isize) -> isize {
-	match x {
-		n if n < 0 => -1,
-		0 => 0,
-		_ => 1,
+fn solve(input: &str, knots: usize) -> Result<usize> {
+	let mut rope = Rope::new(knots, Dims::Two);
+	for (i, l) in input.lines().enumerate() {
+		l.parse()
+			.and_then(|m| rope.apply(m))
+			.map_err(|e| format!("line {}: {e}", i + 1))?;
 	}
+	Ok(rope.tail_visited().len())
 }
 
 #[cfg(test)]
@@ -100,12 +152,12 @@ R 2"#;
 
 	#[test]
 	fn part1_example() {
-		assert_eq!(part1(INPUT), 13);
+		assert_eq!(part1(INPUT).unwrap(), 13);
 	}
 
 	#[test]
 	fn part2_example1() {
-		assert_eq!(part2(INPUT), 1);
+		assert_eq!(part2(INPUT).unwrap(), 1);
 	}
 
 	#[test]
@@ -118,6 +170,12 @@ R 17
 D 10
 L 25
 U 20"#;
-		assert_eq!(part2(input), 36);
+		assert_eq!(part2(input).unwrap(), 36);
+	}
+
+	#[test]
+	fn moves_in_3d() {
+		let err = part1("R 4\nUF 2").unwrap_err();
+		assert_eq!(err.to_string(), "line 2: cannot move along z in 2D");
 	}
 }
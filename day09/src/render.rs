@@ -0,0 +1,262 @@
+use std::collections::HashSet;
+
+use crate::rope::{Move, Pos, Rope};
+use crate::Result;
+
+/// When to capture a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Every {
+	Step,
+	Move,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+	pub title: String,
+	pub knots: Vec<Pos>,
+	/// Tail positions since the previous frame.
+	pub trail: Vec<Pos>,
+}
+
+/// Recorded rope motion, drawn on the x/y plane like in the puzzle.
+#[derive(Debug, Clone)]
+pub struct Animation {
+	frames: Vec<Frame>,
+	min: [isize; 2],
+	max: [isize; 2],
+}
+
+impl Animation {
+	pub fn record(mut rope: Rope, moves: &[Move], every: Every) -> Result<Self> {
+		let mut frames = vec![Frame {
+			title: "Initial State".to_string(),
+			knots: rope.knots().to_vec(),
+			trail: vec![rope.tail()],
+		}];
+		let mut trail = Vec::new();
+		for &m in moves {
+			rope.check(m)?;
+			for i in 0..m.n {
+				rope.step(m.dir);
+				let tail = rope.tail();
+				if trail.last() != Some(&tail) {
+					trail.push(tail);
+				}
+				if every == Every::Step {
+					frames.push(Frame {
+						title: format!("{m} ({}/{})", i + 1, m.n),
+						knots: rope.knots().to_vec(),
+						trail: std::mem::take(&mut trail),
+					});
+				}
+			}
+			if every == Every::Move {
+				frames.push(Frame {
+					title: m.to_string(),
+					knots: rope.knots().to_vec(),
+					trail: std::mem::take(&mut trail),
+				});
+			}
+		}
+
+		// Fits the viewport around everything the rope touches.
+		let mut min = [0, 0];
+		let mut max = [0, 0];
+		for p in frames.iter().flat_map(|f| f.knots.iter().chain(&f.trail)) {
+			for a in 0..2 {
+				min[a] = min[a].min(p[a]);
+				max[a] = max[a].max(p[a]);
+			}
+		}
+
+		Ok(Self { frames, min, max })
+	}
+
+	fn w(&self) -> usize {
+		(self.max[0] - self.min[0] + 1) as usize
+	}
+
+	fn h(&self) -> usize {
+		(self.max[1] - self.min[1] + 1) as usize
+	}
+
+	/// Renders each frame from its cell grid and the cumulative set of visited
+	/// positions, one at a time as the iterator advances.
+	fn render<'a, T>(
+		&'a self,
+		render: impl Fn(&Frame, &[Cell], &[bool]) -> T + 'a,
+	) -> impl Iterator<Item = T> + 'a {
+		let (w, h) = (self.w(), self.h());
+		let index = move |p: &Pos| {
+			let x = (p[0] - self.min[0]) as usize;
+			let y = (p[1] - self.min[1]) as usize;
+			y * w + x
+		};
+		let start = index(&[0; 3]);
+
+		let mut visited = vec![false; w * h];
+		let mut cells = vec![Cell::Empty; w * h];
+		self.frames.iter().map(move |f| {
+			for p in &f.trail {
+				visited[index(p)] = true;
+			}
+			cells.fill(Cell::Empty);
+			cells[start] = Cell::Start;
+			// Knots in front are drawn over the ones behind.
+			for (k, p) in f.knots.iter().enumerate().rev() {
+				cells[index(p)] = Cell::Knot(k, f.knots.len());
+			}
+			render(f, &cells, &visited)
+		})
+	}
+
+	/// Renders the frames as text: a header, then the rope next to the
+	/// visited positions.
+	pub fn to_text(&self) -> impl Iterator<Item = String> + '_ {
+		let w = self.w();
+		self.render(move |f, cells, visited| {
+			let mut text = format!("== {} ==\n", f.title);
+			for (row, seen) in cells.chunks(w).zip(visited.chunks(w)) {
+				text.extend(row.iter().map(Cell::symbol));
+				text.push_str("    ");
+				text.extend(seen.iter().map(|&v| if v { '#' } else { '.' }));
+				text.push('\n');
+			}
+			text
+		})
+	}
+
+	/// Renders the frames as binary PPMs, with each position being a
+	/// `scale`-sized square.
+	pub fn to_ppm(&self, scale: usize) -> impl Iterator<Item = Vec<u8>> + '_ {
+		let (w, h) = (self.w(), self.h());
+		self.render(move |_, cells, visited| {
+			let mut out = format!("P6\n{} {}\n255\n", w * scale, h * scale).into_bytes();
+			for y in 0..h * scale {
+				for x in 0..w * scale {
+					let i = (y / scale) * w + x / scale;
+					out.extend(cells[i].color(visited[i]));
+				}
+			}
+			out
+		})
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cell {
+	Empty,
+	Start,
+	/// Knot index and total number of knots.
+	Knot(usize, usize),
+}
+
+impl Cell {
+	fn symbol(&self) -> char {
+		match *self {
+			Cell::Empty => '.',
+			Cell::Start => 's',
+			Cell::Knot(0, _) => 'H',
+			Cell::Knot(1, 2) => 'T',
+			Cell::Knot(k, _) if k <= 9 => (b'0' + k as u8) as char,
+			Cell::Knot(..) => '*',
+		}
+	}
+
+	fn color(&self, visited: bool) -> [u8; 3] {
+		match *self {
+			Cell::Empty if visited => [0x60, 0x60, 0x70],
+			Cell::Empty => [0x0f, 0x0f, 0x23],
+			Cell::Start => [0x40, 0x80, 0xff],
+			Cell::Knot(0, _) => [0xff, 0x30, 0x30],
+			// Fades from yellow to green towards the tail.
+			Cell::Knot(k, n) => {
+				let t = (k * 0xff / n.max(2).saturating_sub(1)) as u8;
+				[0xff - t, 0xff, 0x30]
+			},
+		}
+	}
+}
+
+/// Cumulative visited map of a single knot on the x/y plane, as drawn in the
+/// puzzle.
+pub fn visited_map(visited: &HashSet<Pos>) -> String {
+	let visited = visited.iter().map(|p| [p[0], p[1]]).collect::<HashSet<_>>();
+	let (mut min, mut max) = ([0, 0], [0, 0]);
+	for p in &visited {
+		for a in 0..2 {
+			min[a] = min[a].min(p[a]);
+			max[a] = max[a].max(p[a]);
+		}
+	}
+
+	let mut text = String::new();
+	for y in min[1]..=max[1] {
+		for x in min[0]..=max[0] {
+			text.push(match [x, y] {
+				[0, 0] => 's',
+				p if visited.contains(&p) => '#',
+				_ => '.',
+			});
+		}
+		text.push('\n');
+	}
+	text
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::rope::Dims;
+
+	fn moves(input: &str) -> Vec<Move> {
+		input.lines().map(|l| l.parse().unwrap()).collect()
+	}
+
+	#[test]
+	fn frames_per_move() {
+		let rope = Rope::new(2, Dims::Two);
+		let animation = Animation::record(rope, &moves("R 4\nU 2"), Every::Move).unwrap();
+		let text = animation.to_text().collect::<Vec<_>>();
+		assert_eq!(text.len(), 3);
+		assert_eq!(
+			text[0],
+			"== Initial State ==\n.....    .....\n.....    .....\nH....    #....\n"
+		);
+		assert_eq!(text[1], "== R 4 ==\n.....    .....\n.....    .....\ns..TH    ####.\n");
+		assert_eq!(text[2], "== U 2 ==\n....H    .....\n....T    ....#\ns....    ####.\n");
+	}
+
+	#[test]
+	fn frames_per_step() {
+		let rope = Rope::new(10, Dims::Two);
+		let animation = Animation::record(rope, &moves("R 2"), Every::Step).unwrap();
+		let text = animation.to_text().collect::<Vec<_>>();
+		assert_eq!(text.len(), 3);
+		assert_eq!(text[1], "== R 2 (1/2) ==\n1H.    #..\n");
+		assert_eq!(text[2], "== R 2 (2/2) ==\n21H    #..\n");
+	}
+
+	#[test]
+	fn images() {
+		let rope = Rope::new(2, Dims::Two);
+		let animation = Animation::record(rope, &moves("L 1"), Every::Step).unwrap();
+		let frames = animation.to_ppm(2).collect::<Vec<_>>();
+		assert_eq!(frames.len(), 2);
+		let header = b"P6\n4 2\n255\n";
+		assert!(frames[1].starts_with(header));
+		assert_eq!(frames[1].len(), header.len() + 4 * 2 * 3);
+		// The head is on the left, the tail on the right.
+		assert_eq!(frames[1][header.len()..header.len() + 3], [0xff, 0x30, 0x30]);
+	}
+
+	#[test]
+	fn visited() {
+		let mut rope = Rope::new(2, Dims::Two);
+		for m in moves("R 4\nU 4\nL 3\nD 1\nR 4\nD 1\nL 5\nR 2") {
+			rope.apply(m).unwrap();
+		}
+		let map = "..##.\n...##\n.####\n....#\ns###.\n";
+		assert_eq!(visited_map(rope.tail_visited()), map);
+	}
+}
@@ -0,0 +1,200 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{Error, Result};
+
+pub type Pos = [isize; 3];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dims {
+	Two,
+	Three,
+}
+
+/// Head movement by a unit step in `dir` repeated `n` times. Directions are
+/// combinations of `R`/`L`, `U`/`D` and, in 3D, `F`/`B`, e.g. `UR` or `DLF`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Move {
+	pub dir: Pos,
+	pub n: usize,
+}
+
+impl FromStr for Move {
+	type Err = Error;
+
+	fn from_str(s: &str) -> Result<Self> {
+		let (d, n) = s
+			.split_once(' ')
+			.ok_or_else(|| format!("invalid move `{s}`"))?;
+		let mut dir = [0; 3];
+		for c in d.bytes() {
+			let (axis, v) = match c {
+				b'R' => (0, 1),
+				b'L' => (0, -1),
+				b'U' => (1, -1),
+				b'D' => (1, 1),
+				b'F' => (2, 1),
+				b'B' => (2, -1),
+				_ => return Err(format!("invalid direction `{d}`").into()),
+			};
+			if dir[axis] != 0 {
+				return Err(format!("conflicting direction `{d}`").into());
+			}
+			dir[axis] = v;
+		}
+		if dir == [0; 3] {
+			return Err(format!("missing direction in `{s}`").into());
+		}
+		Ok(Move {
+			dir,
+			n: n.trim().parse()?,
+		})
+	}
+}
+
+impl fmt::Display for Move {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let letters = [['L', 'R'], ['U', 'D'], ['B', 'F']];
+		// Vertical first, to match the usual `UR` and `DL` spelling.
+		for axis in [1, 0, 2] {
+			match self.dir[axis] {
+				-1 => write!(f, "{}", letters[axis][0])?,
+				1 => write!(f, "{}", letters[axis][1])?,
+				_ => {},
+			}
+		}
+		write!(f, " {}", self.n)
+	}
+}
+
+/// Rope of any number of knots, keeping track of where each knot has been.
+#[derive(Debug, Clone)]
+pub struct Rope {
+	dims: Dims,
+	knots: Vec<Pos>,
+	visited: Vec<HashSet<Pos>>,
+}
+
+impl Rope {
+	pub fn new(knots: usize, dims: Dims) -> Self {
+		assert!(knots > 0, "rope should have at least one knot");
+		Self {
+			dims,
+			knots: vec![[0; 3]; knots],
+			visited: vec![HashSet::from([[0; 3]]); knots],
+		}
+	}
+
+	pub fn knots(&self) -> &[Pos] {
+		&self.knots
+	}
+
+	/// Positions visited by each knot, head first.
+	pub fn visited(&self) -> &[HashSet<Pos>] {
+		&self.visited
+	}
+
+	pub fn tail(&self) -> Pos {
+		self.knots[self.knots.len() - 1]
+	}
+
+	pub fn tail_visited(&self) -> &HashSet<Pos> {
+		&self.visited[self.visited.len() - 1]
+	}
+
+	/// Checks that the move stays within the rope's dimensions.
+	pub fn check(&self, m: Move) -> Result<()> {
+		if self.dims == Dims::Two && m.dir[2] != 0 {
+			return Err("cannot move along z in 2D".into());
+		}
+		Ok(())
+	}
+
+	pub fn apply(&mut self, m: Move) -> Result<()> {
+		self.check(m)?;
+		for _ in 0..m.n {
+			self.step(m.dir);
+		}
+		Ok(())
+	}
+
+	/// Moves the head by `dir`, pulling the rest of the knots along.
+	pub fn step(&mut self, dir: Pos) {
+		self.knots[0] = add(self.knots[0], dir);
+		self.visited[0].insert(self.knots[0]);
+		for k in 1..self.knots.len() {
+			let next = follow(self.knots[k - 1], self.knots[k]);
+			if next == self.knots[k] {
+				// Nothing behind moves either.
+				break;
+			}
+			self.knots[k] = next;
+			self.visited[k].insert(next);
+		}
+	}
+}
+
+fn add(a: Pos, b: Pos) -> Pos {
+	[a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+/// Keeps the tail touching the head: if it's more than a step away along any
+/// axis, the tail moves a step towards the head along every axis it differs.
+pub fn follow(head: Pos, tail: Pos) -> Pos {
+	let d = [head[0] - tail[0], head[1] - tail[1], head[2] - tail[2]];
+	if d.iter().all(|d| d.abs() <= 1) {
+		tail
+	} else {
+		add(tail, d.map(isize::signum))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn run(rope: &mut Rope, moves: &str) {
+		for m in moves.lines() {
+			rope.apply(m.parse().unwrap()).unwrap();
+		}
+	}
+
+	#[test]
+	fn moves() {
+		assert_eq!("R 4".parse::<Move>().unwrap(), Move { dir: [1, 0, 0], n: 4 });
+		assert_eq!("UL 2".parse::<Move>().unwrap(), Move { dir: [-1, -1, 0], n: 2 });
+		assert_eq!("DRB 1".parse::<Move>().unwrap(), Move { dir: [1, 1, -1], n: 1 });
+		assert!("RL 1".parse::<Move>().is_err());
+		assert!("X 1".parse::<Move>().is_err());
+		assert!("R".parse::<Move>().is_err());
+		assert_eq!("RU 3".parse::<Move>().unwrap().to_string(), "UR 3");
+	}
+
+	#[test]
+	fn every_knot_is_tracked() {
+		let mut rope = Rope::new(3, Dims::Two);
+		run(&mut rope, "R 4");
+		assert_eq!(rope.knots(), [[4, 0, 0], [3, 0, 0], [2, 0, 0]]);
+		let counts = rope.visited().iter().map(HashSet::len).collect::<Vec<_>>();
+		assert_eq!(counts, [5, 4, 3]);
+	}
+
+	#[test]
+	fn diagonal() {
+		let mut rope = Rope::new(2, Dims::Two);
+		run(&mut rope, "UR 3\nDL 1");
+		assert_eq!(rope.knots(), [[2, -2, 0], [2, -2, 0]]);
+		assert_eq!(rope.tail_visited().len(), 3);
+	}
+
+	#[test]
+	fn three_dimensions() {
+		let mut rope = Rope::new(2, Dims::Two);
+		assert!(rope.apply("F 1".parse().unwrap()).is_err());
+
+		let mut rope = Rope::new(3, Dims::Three);
+		run(&mut rope, "F 3\nUR 2");
+		assert_eq!(rope.knots(), [[2, -2, 3], [1, -1, 3], [1, -1, 2]]);
+	}
+}
@@ -0,0 +1,290 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::rope::{follow, Dims, Move, Pos};
+use crate::Result;
+
+/// Set of lattice points made of straight segments along unit directions,
+/// including diagonals, and lone points.
+#[derive(Debug, Clone, Default)]
+pub struct Trail {
+	/// Segments grouped by direction and then by the line they lie on, as
+	/// ranges of the position along it.
+	lines: HashMap<Pos, HashMap<Pos, Vec<(isize, isize)>>>,
+	points: HashSet<Pos>,
+}
+
+impl Trail {
+	pub fn point(&mut self, p: Pos) {
+		self.points.insert(p);
+	}
+
+	/// Adds `len + 1` points, starting from `from` and going along `dir`.
+	pub fn segment(&mut self, from: Pos, dir: Pos, len: usize) {
+		if len == 0 || dir == [0; 3] {
+			self.point(from);
+			return;
+		}
+		let (forward, a) = normalize(dir);
+		let (key, t) = line(from, forward, a);
+		let end = t + len as isize * dir[a];
+		let range = (t.min(end), t.max(end));
+		self.lines.entry(forward).or_default().entry(key).or_default().push(range);
+	}
+
+	/// Number of distinct points, counted without expanding segments.
+	pub fn len(&self) -> usize {
+		let mut lines = self.lines.clone();
+		for ranges in lines.values_mut().flat_map(HashMap::values_mut) {
+			merge(ranges);
+		}
+
+		let mut total = lines
+			.values()
+			.flat_map(HashMap::values)
+			.flatten()
+			.map(|(a, b)| (b - a + 1) as usize)
+			.sum::<usize>();
+
+		// Lines along different directions cross at most once, so points on
+		// several of them are found pairwise and are overcounted by the number
+		// of extra directions they are on.
+		let dirs = lines.iter().collect::<Vec<_>>();
+		let mut shared = HashMap::<Pos, u32>::new();
+		for i in 0..dirs.len() {
+			for j in i + 1..dirs.len() {
+				let ((d1, l1), (d2, l2)) = (dirs[i], dirs[j]);
+				for (k1, r1) in l1 {
+					for (k2, r2) in l2 {
+						let Some((t, s)) = cross(*k1, *d1, *k2, *d2) else {
+							continue;
+						};
+						if within(r1, t) && within(r2, s) {
+							let p = add(*k1, scale(*d1, t));
+							*shared.entry(p).or_default() |= 1 << i | 1 << j;
+						}
+					}
+				}
+			}
+		}
+		total -= shared.values().map(|m| m.count_ones() as usize - 1).sum::<usize>();
+
+		let covered = |p: Pos| {
+			lines.iter().any(|(&dir, l)| {
+				let (_, a) = normalize(dir);
+				let (key, t) = line(p, dir, a);
+				l.get(&key).is_some_and(|rs| within(rs, t))
+			})
+		};
+		total + self.points.iter().filter(|&&p| !covered(p)).count()
+	}
+}
+
+/// Flips the direction so its first non-zero component is positive,
+/// returning it with the index of that component.
+fn normalize(dir: Pos) -> (Pos, usize) {
+	let a = dir.iter().position(|&d| d != 0).unwrap_or(0);
+	if dir[a] < 0 {
+		(dir.map(|d| -d), a)
+	} else {
+		(dir, a)
+	}
+}
+
+/// Identifies the line through `p` along normalized `dir` by its point with
+/// zero on axis `a`, and returns the position of `p` along it.
+fn line(p: Pos, dir: Pos, a: usize) -> (Pos, isize) {
+	let t = p[a];
+	(sub(p, scale(dir, t)), t)
+}
+
+/// Whether `t` falls into one of the sorted, disjoint `ranges`.
+fn within(ranges: &[(isize, isize)], t: isize) -> bool {
+	let i = ranges.partition_point(|&(_, b)| b < t);
+	ranges.get(i).is_some_and(|&(a, _)| a <= t)
+}
+
+fn merge(ranges: &mut Vec<(isize, isize)>) {
+	ranges.sort_unstable();
+	let mut merged: Vec<(isize, isize)> = Vec::with_capacity(ranges.len());
+	for &(a, b) in ranges.iter() {
+		match merged.last_mut() {
+			Some((_, e)) if a <= *e + 1 => *e = (*e).max(b),
+			_ => merged.push((a, b)),
+		}
+	}
+	*ranges = merged;
+}
+
+/// Finds where `p + t * d1` meets `q + s * d2` at a lattice point.
+fn cross(p: Pos, d1: Pos, q: Pos, d2: Pos) -> Option<(isize, isize)> {
+	let r = sub(q, p);
+	for (i, j) in [(0, 1), (0, 2), (1, 2)] {
+		let det = d2[i] * d1[j] - d1[i] * d2[j];
+		if det == 0 {
+			continue;
+		}
+		let t = d2[i] * r[j] - r[i] * d2[j];
+		let s = d1[i] * r[j] - d1[j] * r[i];
+		if t % det != 0 || s % det != 0 {
+			return None;
+		}
+		let (t, s) = (t / det, s / det);
+		return (add(p, scale(d1, t)) == add(q, scale(d2, s))).then_some((t, s));
+	}
+	None
+}
+
+fn add(a: Pos, b: Pos) -> Pos {
+	[a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+fn sub(a: Pos, b: Pos) -> Pos {
+	[a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn scale(a: Pos, k: isize) -> Pos {
+	a.map(|x| x * k)
+}
+
+/// Rope that, once it's straightened out along a move, shifts all of its
+/// knots by the rest of the move at once, so it handles moves of any length.
+#[derive(Debug, Clone)]
+pub struct SegmentRope {
+	dims: Dims,
+	knots: Vec<Pos>,
+	visited: Vec<Trail>,
+}
+
+impl SegmentRope {
+	pub fn new(knots: usize, dims: Dims) -> Self {
+		assert!(knots > 0, "rope should have at least one knot");
+		let mut trail = Trail::default();
+		trail.point([0; 3]);
+		Self {
+			dims,
+			knots: vec![[0; 3]; knots],
+			visited: vec![trail; knots],
+		}
+	}
+
+	/// Number of positions visited by each knot, head first.
+	pub fn visited(&self) -> Vec<usize> {
+		self.visited.iter().map(Trail::len).collect()
+	}
+
+	pub fn apply(&mut self, m: Move) -> Result<()> {
+		if self.dims == Dims::Two && m.dir[2] != 0 {
+			return Err("cannot move along z in 2D".into());
+		}
+
+		let mut left = m.n;
+		while left > 0 {
+			let before = self.knots.clone();
+			self.knots[0] = add(self.knots[0], m.dir);
+			for k in 1..self.knots.len() {
+				self.knots[k] = follow(self.knots[k - 1], self.knots[k]);
+			}
+			left -= 1;
+
+			let straight = self.knots.iter().zip(&before).all(|(&k, &b)| sub(k, b) == m.dir);
+			for (k, trail) in self.knots.iter().zip(&mut self.visited) {
+				if straight {
+					// Every step from now on moves the whole rope the same way.
+					trail.segment(*k, m.dir, left);
+				} else {
+					trail.point(*k);
+				}
+			}
+			if straight {
+				for k in &mut self.knots {
+					*k = add(*k, scale(m.dir, left as isize));
+				}
+				left = 0;
+			}
+		}
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::rope::Rope;
+
+	/// Every move along one of the directions in `dims`, by each of `lengths`.
+	fn moves(dims: Dims, lengths: &[usize]) -> Vec<Move> {
+		(0..27)
+			.map(|i| [i % 3 - 1, i / 3 % 3 - 1, i / 9 - 1])
+			.filter(|&dir| dir != [0; 3] && (dims == Dims::Three || dir[2] == 0))
+			.flat_map(|dir| lengths.iter().map(move |&n| Move { dir, n }))
+			.collect()
+	}
+
+	#[test]
+	fn trail_counts_unions() {
+		let mut trail = Trail::default();
+		trail.segment([0, 0, 0], [1, 0, 0], 4);
+		trail.segment([6, 0, 0], [-1, 0, 0], 3);
+		trail.segment([2, -2, 0], [0, 1, 0], 4);
+		trail.segment([0, 0, 0], [1, 1, 0], 2);
+		trail.point([2, 2, 0]);
+		trail.point([9, 9, 9]);
+		// 7 along x, 4 more along y, a single new one diagonally and a lone
+		// point, since `[2, 2, 0]` is on the y segment.
+		assert_eq!(trail.len(), 7 + 4 + 1 + 1);
+	}
+
+	#[test]
+	fn matches_step_by_step() {
+		// Every sequence of three moves in 2D and of two in 3D.
+		let mut runs = Vec::new();
+		let flat = moves(Dims::Two, &[1, 6]);
+		for &a in &flat {
+			for &b in &flat {
+				for &c in &flat {
+					runs.push((vec![a, b, c], Dims::Two));
+				}
+			}
+		}
+		let deep = moves(Dims::Three, &[1, 3]);
+		for &a in &deep {
+			for &b in &deep {
+				runs.push((vec![a, b], Dims::Three));
+			}
+		}
+
+		for (moves, dims) in runs {
+			for knots in [1, 2, 5, 10] {
+				let mut rope = Rope::new(knots, dims);
+				let mut fast = SegmentRope::new(knots, dims);
+				for &m in &moves {
+					rope.apply(m).unwrap();
+					fast.apply(m).unwrap();
+				}
+				let counts = rope.visited().iter().map(HashSet::len).collect::<Vec<_>>();
+				assert_eq!(fast.visited(), counts, "{moves:?}, {knots} knots");
+				assert_eq!(fast.knots, rope.knots());
+			}
+		}
+	}
+
+	#[test]
+	fn huge_moves() {
+		let square = |n: usize| ["R", "U", "L", "D"].map(|d| format!("{d} {n}").parse().unwrap());
+
+		let mut rope = Rope::new(10, Dims::Two);
+		for m in square(100) {
+			rope.apply(m).unwrap();
+		}
+		let mut fast = SegmentRope::new(10, Dims::Two);
+		for m in square(1_000_000_000) {
+			fast.apply(m).unwrap();
+		}
+		// Only the sides get longer, the corners stay the same.
+		assert_eq!(
+			fast.visited()[9] as isize - 4 * 1_000_000_000,
+			rope.tail_visited().len() as isize - 4 * 100
+		);
+	}
+}
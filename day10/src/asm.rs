@@ -0,0 +1,284 @@
+//! Text and bytecode forms of CPU programs.
+//!
+//! Source has an instruction or a `label:` per line, with comments starting
+//! at `;` or `#`. Bytecode is a stream of opcodes: `0x00` for `noop`, `0x01`
+//! followed by a zigzag LEB128 operand for `addx`, and `0xfe` followed by a
+//! length byte and a name for a label at the current address.
+
+use std::error;
+use std::fmt;
+
+use crate::cpu::Instruction;
+
+const NOOP: u8 = 0x00;
+const ADDX: u8 = 0x01;
+const LABEL: u8 = 0xfe;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Program {
+	pub code: Vec<Instruction>,
+	/// Labels with the address of the instruction they precede, in order.
+	pub labels: Vec<(String, usize)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AsmErrorKind {
+	UnknownMnemonic(String),
+	MissingOperand,
+	InvalidOperand(String),
+	UnexpectedOperand(String),
+	InvalidLabel(String),
+	DuplicateLabel(String),
+}
+
+/// Error in program source, with a line number starting from 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AsmError {
+	pub line: usize,
+	pub kind: AsmErrorKind,
+}
+
+impl fmt::Display for AsmError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "line {}: ", self.line)?;
+		match &self.kind {
+			AsmErrorKind::UnknownMnemonic(m) => write!(f, "unknown mnemonic `{m}`"),
+			AsmErrorKind::MissingOperand => write!(f, "missing operand"),
+			AsmErrorKind::InvalidOperand(o) => write!(f, "invalid operand `{o}`"),
+			AsmErrorKind::UnexpectedOperand(o) => write!(f, "unexpected operand `{o}`"),
+			AsmErrorKind::InvalidLabel(l) => write!(f, "invalid label `{l}`"),
+			AsmErrorKind::DuplicateLabel(l) => write!(f, "duplicate label `{l}`"),
+		}
+	}
+}
+
+impl error::Error for AsmError {}
+
+/// Malformed bytecode, with the offset of the offending opcode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BytecodeError {
+	UnknownOpcode { offset: usize, op: u8 },
+	Truncated { offset: usize },
+}
+
+impl fmt::Display for BytecodeError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			BytecodeError::UnknownOpcode { offset, op } => {
+				write!(f, "unknown opcode {op:#04x} at offset {offset}")
+			},
+			BytecodeError::Truncated { offset } => {
+				write!(f, "truncated instruction at offset {offset}")
+			},
+		}
+	}
+}
+
+impl error::Error for BytecodeError {}
+
+pub fn assemble(source: &str) -> Result<Program, AsmError> {
+	let mut program = Program::default();
+	for (i, line) in source.lines().enumerate() {
+		let err = |kind| AsmError { line: i + 1, kind };
+
+		let line = line.split([';', '#']).next().unwrap_or_default().trim();
+		if line.is_empty() {
+			continue;
+		}
+
+		if let Some(label) = line.strip_suffix(':') {
+			let label = label.trim();
+			if !valid_label(label) {
+				return Err(err(AsmErrorKind::InvalidLabel(label.to_string())));
+			}
+			if program.label(label).is_some() {
+				return Err(err(AsmErrorKind::DuplicateLabel(label.to_string())));
+			}
+			program.labels.push((label.to_string(), program.code.len()));
+			continue;
+		}
+
+		program.code.push(line.parse().map_err(err)?);
+	}
+
+	Ok(program)
+}
+
+fn valid_label(label: &str) -> bool {
+	!label.is_empty()
+		&& label.len() <= u8::MAX as usize
+		&& label.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_')
+}
+
+impl Program {
+	pub fn label(&self, name: &str) -> Option<usize> {
+		self.labels.iter().find(|(l, _)| l == name).map(|&(_, a)| a)
+	}
+
+	pub fn to_bytes(&self) -> Vec<u8> {
+		let mut out = Vec::new();
+		let mut labels = self.labels.iter().peekable();
+		for (a, i) in self.code.iter().enumerate() {
+			while let Some((l, _)) = labels.next_if(|&(_, la)| *la == a) {
+				push_label(&mut out, l);
+			}
+			match *i {
+				Instruction::Noop => out.push(NOOP),
+				Instruction::Addx(v) => {
+					out.push(ADDX);
+					push_varint(&mut out, v as i64);
+				},
+			}
+		}
+		// Labels past the last instruction.
+		for (l, _) in labels {
+			push_label(&mut out, l);
+		}
+		out
+	}
+
+	pub fn from_bytes(bytes: &[u8]) -> Result<Self, BytecodeError> {
+		let mut program = Program::default();
+		let mut at = 0;
+		while let Some(&op) = bytes.get(at) {
+			let offset = at;
+			let truncated = BytecodeError::Truncated { offset };
+			at += 1;
+			match op {
+				NOOP => program.code.push(Instruction::Noop),
+				ADDX => {
+					let (v, len) = read_varint(&bytes[at..]).ok_or(truncated)?;
+					at += len;
+					program.code.push(Instruction::Addx(v as isize));
+				},
+				LABEL => {
+					let len = *bytes.get(at).ok_or(truncated.clone())? as usize;
+					let name = bytes.get(at + 1..at + 1 + len).ok_or(truncated)?;
+					at += 1 + len;
+					let name = String::from_utf8_lossy(name).into_owned();
+					program.labels.push((name, program.code.len()));
+				},
+				op => return Err(BytecodeError::UnknownOpcode { offset, op }),
+			}
+		}
+		Ok(program)
+	}
+}
+
+/// Disassembles the program back to source.
+impl fmt::Display for Program {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let mut labels = self.labels.iter().peekable();
+		for (a, i) in self.code.iter().enumerate() {
+			while let Some((l, _)) = labels.next_if(|&(_, la)| *la == a) {
+				writeln!(f, "{l}:")?;
+			}
+			writeln!(f, "\t{i}")?;
+		}
+		for (l, _) in labels {
+			writeln!(f, "{l}:")?;
+		}
+		Ok(())
+	}
+}
+
+fn push_label(out: &mut Vec<u8>, name: &str) {
+	out.push(LABEL);
+	out.push(name.len() as u8);
+	out.extend(name.bytes());
+}
+
+fn push_varint(out: &mut Vec<u8>, v: i64) {
+	let mut z = ((v << 1) ^ (v >> 63)) as u64;
+	loop {
+		let b = (z & 0x7f) as u8;
+		z >>= 7;
+		if z == 0 {
+			out.push(b);
+			return;
+		}
+		out.push(b | 0x80);
+	}
+}
+
+fn read_varint(bytes: &[u8]) -> Option<(i64, usize)> {
+	let mut z = 0u64;
+	for (i, &b) in bytes.iter().enumerate().take(10) {
+		z |= ((b & 0x7f) as u64) << (7 * i);
+		if b & 0x80 == 0 {
+			let v = (z >> 1) as i64 ^ -((z & 1) as i64);
+			return Some((v, i + 1));
+		}
+	}
+	None
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const SOURCE: &str = "
+		; Moves the sprite around a bit.
+		start:
+			addx 15   # right
+			noop
+		back:
+			addx -300
+		end:
+	";
+
+	#[test]
+	fn assembles() {
+		let program = assemble(SOURCE).unwrap();
+		assert_eq!(
+			program.code,
+			[Instruction::Addx(15), Instruction::Noop, Instruction::Addx(-300)]
+		);
+		assert_eq!(program.label("back"), Some(2));
+		assert_eq!(program.label("end"), Some(3));
+	}
+
+	#[test]
+	fn round_trips() {
+		let program = assemble(SOURCE).unwrap();
+		let bytes = program.to_bytes();
+		assert_eq!(&bytes[bytes.len() - 5..], [LABEL, 3, b'e', b'n', b'd']);
+		assert_eq!(Program::from_bytes(&bytes), Ok(program.clone()));
+		assert_eq!(assemble(&program.to_string()), Ok(program));
+	}
+
+	#[test]
+	fn varints() {
+		for v in [0, 1, -1, 63, -64, 64, i32::MAX as i64, i64::MIN, i64::MAX] {
+			let mut out = Vec::new();
+			push_varint(&mut out, v);
+			assert_eq!(read_varint(&out), Some((v, out.len())), "{v}");
+		}
+	}
+
+	#[test]
+	fn errors() {
+		let err = |source| assemble(source).unwrap_err();
+		assert_eq!(
+			err("noop\nmulx 2"),
+			AsmError {
+				line: 2,
+				kind: AsmErrorKind::UnknownMnemonic("mulx".to_string()),
+			}
+		);
+		assert_eq!(err("addx").kind, AsmErrorKind::MissingOperand);
+		assert_eq!(err("addx x").kind, AsmErrorKind::InvalidOperand("x".to_string()));
+		assert_eq!(err("noop 1").kind, AsmErrorKind::UnexpectedOperand("1".to_string()));
+		assert_eq!(err("a b:").kind, AsmErrorKind::InvalidLabel("a b".to_string()));
+		assert_eq!(err("a:\na:").line, 2);
+
+		assert_eq!(
+			Program::from_bytes(&[NOOP, 0x42]),
+			Err(BytecodeError::UnknownOpcode { offset: 1, op: 0x42 })
+		);
+		assert_eq!(
+			Program::from_bytes(&[ADDX, 0x80]),
+			Err(BytecodeError::Truncated { offset: 0 })
+		);
+	}
+}
@@ -0,0 +1,383 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::asm::AsmErrorKind;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+	Noop,
+	Addx(isize),
+}
+
+/// Parses a single instruction, e.g. `addx -3`.
+impl FromStr for Instruction {
+	type Err = AsmErrorKind;
+
+	fn from_str(s: &str) -> Result<Self, AsmErrorKind> {
+		let mut parts = s.split_whitespace();
+		let i = match parts.next().unwrap_or_default() {
+			"noop" => Instruction::Noop,
+			"addx" => {
+				let v = parts.next().ok_or(AsmErrorKind::MissingOperand)?;
+				let v = v
+					.parse()
+					.map_err(|_| AsmErrorKind::InvalidOperand(v.to_string()))?;
+				Instruction::Addx(v)
+			},
+			m => return Err(AsmErrorKind::UnknownMnemonic(m.to_string())),
+		};
+		match parts.next() {
+			None => Ok(i),
+			Some(o) => Err(AsmErrorKind::UnexpectedOperand(o.to_string())),
+		}
+	}
+}
+
+impl fmt::Display for Instruction {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Instruction::Noop => write!(f, "noop"),
+			Instruction::Addx(v) => write!(f, "addx {v}"),
+		}
+	}
+}
+
+/// Number of cycles each instruction takes to complete.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Costs {
+	pub noop: usize,
+	pub addx: usize,
+}
+
+impl Costs {
+	pub fn of(&self, i: Instruction) -> usize {
+		match i {
+			Instruction::Noop => self.noop,
+			Instruction::Addx(_) => self.addx,
+		}
+	}
+}
+
+impl Default for Costs {
+	fn default() -> Self {
+		Self { noop: 1, addx: 2 }
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Registers {
+	pub x: isize,
+}
+
+impl Default for Registers {
+	fn default() -> Self {
+		Self { x: 1 }
+	}
+}
+
+/// Whether a hook observes the registers while a cycle is executing or once
+/// it has finished.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+	During,
+	After,
+}
+
+/// Snapshot of the machine at some cycle. Cycles are numbered from 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct State {
+	pub cycle: usize,
+	pub pc: usize,
+	pub regs: Registers,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Breakpoint {
+	/// Stops before the given cycle starts.
+	Cycle(usize),
+	/// Stops before the instruction at the given address starts.
+	Pc(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stop {
+	Halted,
+	Breakpoint(Breakpoint),
+	/// `X` overflowed retiring the instruction at the given address, which
+	/// stops the machine for good.
+	Overflow(usize),
+}
+
+pub struct Cpu {
+	program: Vec<Instruction>,
+	costs: Costs,
+	regs: Registers,
+	pc: usize,
+	cycle: usize,
+	// Cycles already spent on the instruction at `pc`.
+	busy: usize,
+	breakpoints: HashSet<Breakpoint>,
+	// Breakpoints already stopped at before the next cycle, so resuming
+	// doesn't stop at them again.
+	resumed: HashSet<Breakpoint>,
+	// Address of the instruction that overflowed `X`.
+	overflow: Option<usize>,
+	trace: Option<Vec<String>>,
+}
+
+impl Cpu {
+	pub fn new(program: Vec<Instruction>) -> Self {
+		Self::with_costs(program, Costs::default())
+	}
+
+	pub fn with_costs(program: Vec<Instruction>, costs: Costs) -> Self {
+		Self {
+			program,
+			costs,
+			regs: Registers::default(),
+			pc: 0,
+			cycle: 0,
+			busy: 0,
+			breakpoints: HashSet::new(),
+			resumed: HashSet::new(),
+			overflow: None,
+			trace: None,
+		}
+	}
+
+	pub fn state(&self) -> State {
+		State {
+			cycle: self.cycle,
+			pc: self.pc,
+			regs: self.regs,
+		}
+	}
+
+	/// Whether the program has run to its end or stopped on an overflow.
+	pub fn halted(&self) -> bool {
+		self.pc >= self.program.len() || self.overflow.is_some()
+	}
+
+	/// Address of the instruction that overflowed `X`, if any.
+	pub fn overflow(&self) -> Option<usize> {
+		self.overflow
+	}
+
+	pub fn add_breakpoint(&mut self, b: Breakpoint) {
+		self.breakpoints.insert(b);
+	}
+
+	/// Enables recording of a line per executed cycle.
+	pub fn set_trace(&mut self, on: bool) {
+		self.trace = on.then(Vec::new);
+	}
+
+	pub fn trace(&self) -> &[String] {
+		self.trace.as_deref().unwrap_or_default()
+	}
+
+	/// Executes a single cycle, calling `hook` with the state during the cycle
+	/// and then after it. Returns `false` if the program has already halted.
+	pub fn step<F>(&mut self, mut hook: F) -> bool
+	where
+		F: FnMut(Phase, &State),
+	{
+		// Skips over instructions that cost nothing.
+		while !self.halted() && self.costs.of(self.program[self.pc]) == 0 {
+			self.retire();
+		}
+		if self.halted() {
+			return false;
+		}
+		let i = self.program[self.pc];
+
+		self.cycle += 1;
+		self.busy += 1;
+		self.resumed.clear();
+		let during = self.state();
+		hook(Phase::During, &during);
+
+		let cost = self.costs.of(i);
+		if let Some(trace) = &mut self.trace {
+			trace.push(format!(
+				"{:>4} pc={:<4} x={:<4} {i} ({}/{cost})",
+				during.cycle, during.pc, during.regs.x, self.busy,
+			));
+		}
+		if self.busy == cost {
+			self.retire();
+		}
+		hook(Phase::After, &self.state());
+
+		true
+	}
+
+	/// Runs until the program halts or a breakpoint is hit. Each call stops
+	/// at a single breakpoint: when several are hit at once, the next call
+	/// stops at the next one without executing anything, cycles first.
+	pub fn run<F>(&mut self, mut hook: F) -> Stop
+	where
+		F: FnMut(Phase, &State),
+	{
+		loop {
+			if let Some(pc) = self.overflow {
+				return Stop::Overflow(pc);
+			}
+			let hit = self.hits().find(|b| !self.resumed.contains(b));
+			if let Some(b) = hit {
+				self.resumed.insert(b);
+				return Stop::Breakpoint(b);
+			}
+			// Retires instructions that cost nothing one at a time, so their
+			// breakpoints get a chance to stop.
+			if !self.halted() && self.costs.of(self.program[self.pc]) == 0 {
+				self.retire();
+			} else if !self.step(&mut hook) {
+				return Stop::Halted;
+			}
+		}
+	}
+
+	/// Breakpoints matching the current state.
+	fn hits(&self) -> impl Iterator<Item = Breakpoint> + '_ {
+		let cycle = Breakpoint::Cycle(self.cycle + 1);
+		let pc = (self.busy == 0).then_some(Breakpoint::Pc(self.pc));
+		[Some(cycle), pc]
+			.into_iter()
+			.flatten()
+			.filter(|b| self.breakpoints.contains(b))
+	}
+
+	fn retire(&mut self) {
+		if let Instruction::Addx(v) = self.program[self.pc] {
+			let Some(x) = self.regs.x.checked_add(v) else {
+				self.overflow = Some(self.pc);
+				return;
+			};
+			self.regs.x = x;
+		}
+		self.pc += 1;
+		self.busy = 0;
+	}
+}
+
+/// Yields the state during each cycle until the program halts.
+impl Iterator for Cpu {
+	type Item = State;
+
+	fn next(&mut self) -> Option<State> {
+		let mut state = None;
+		self.step(|phase, s| {
+			if phase == Phase::During {
+				state = Some(*s);
+			}
+		});
+		state
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn program(input: &str) -> Vec<Instruction> {
+		crate::asm::assemble(input).unwrap().code
+	}
+
+	#[test]
+	fn small_program() {
+		let cpu = Cpu::new(program("noop\naddx 3\naddx -5"));
+		let xs = cpu.map(|s| (s.cycle, s.regs.x)).collect::<Vec<_>>();
+		assert_eq!(xs, [(1, 1), (2, 1), (3, 1), (4, 4), (5, 4)]);
+	}
+
+	#[test]
+	fn phases() {
+		let mut cpu = Cpu::new(program("noop\naddx 3\naddx -5"));
+		let mut after = Vec::new();
+		cpu.run(|phase, s| {
+			if phase == Phase::After {
+				after.push(s.regs.x);
+			}
+		});
+		assert_eq!(after, [1, 1, 4, 4, -1]);
+		assert!(cpu.halted());
+	}
+
+	#[test]
+	fn breakpoints() {
+		let mut cpu = Cpu::new(program("noop\naddx 3\naddx -5"));
+		cpu.add_breakpoint(Breakpoint::Cycle(3));
+		cpu.add_breakpoint(Breakpoint::Pc(2));
+
+		assert_eq!(cpu.run(|_, _| {}), Stop::Breakpoint(Breakpoint::Cycle(3)));
+		assert_eq!(cpu.state().cycle, 2);
+		assert_eq!(cpu.run(|_, _| {}), Stop::Breakpoint(Breakpoint::Pc(2)));
+		assert_eq!(cpu.state().regs.x, 4);
+		assert_eq!(cpu.run(|_, _| {}), Stop::Halted);
+		assert_eq!(cpu.state().regs.x, -1);
+	}
+
+	#[test]
+	fn simultaneous_breakpoints() {
+		let mut cpu = Cpu::new(program("noop\naddx 3\naddx -5"));
+		cpu.add_breakpoint(Breakpoint::Cycle(2));
+		cpu.add_breakpoint(Breakpoint::Pc(1));
+
+		assert_eq!(cpu.run(|_, _| {}), Stop::Breakpoint(Breakpoint::Cycle(2)));
+		assert_eq!(cpu.run(|_, _| {}), Stop::Breakpoint(Breakpoint::Pc(1)));
+		assert_eq!(cpu.state().cycle, 1);
+		assert_eq!(cpu.run(|_, _| {}), Stop::Halted);
+	}
+
+	#[test]
+	fn free_instruction_breakpoints() {
+		let costs = Costs { noop: 0, addx: 2 };
+		let mut cpu = Cpu::with_costs(program("noop\naddx 3\nnoop\nnoop"), costs);
+		cpu.add_breakpoint(Breakpoint::Pc(0));
+		cpu.add_breakpoint(Breakpoint::Pc(3));
+		cpu.add_breakpoint(Breakpoint::Cycle(3));
+
+		assert_eq!(cpu.run(|_, _| {}), Stop::Breakpoint(Breakpoint::Pc(0)));
+		assert_eq!(cpu.state().cycle, 0);
+		// Only free `noop`s are left, so the third cycle never starts.
+		assert_eq!(cpu.run(|_, _| {}), Stop::Breakpoint(Breakpoint::Cycle(3)));
+		assert_eq!(cpu.state().pc, 2);
+		assert_eq!(cpu.run(|_, _| {}), Stop::Breakpoint(Breakpoint::Pc(3)));
+		assert_eq!(cpu.state().regs.x, 4);
+		assert_eq!(cpu.run(|_, _| {}), Stop::Halted);
+	}
+
+	#[test]
+	fn overflow() {
+		let mut cpu = Cpu::new(program("addx 9223372036854775806\nnoop\naddx 1\nnoop"));
+		assert_eq!(cpu.by_ref().count(), 5);
+		assert_eq!(cpu.overflow(), Some(2));
+		assert!(cpu.halted());
+		assert_eq!(cpu.state().regs.x, isize::MAX);
+
+		let mut cpu = Cpu::new(program("addx -9223372036854775807\naddx -3\nnoop"));
+		assert_eq!(cpu.run(|_, _| {}), Stop::Overflow(1));
+		assert_eq!(cpu.run(|_, _| {}), Stop::Overflow(1));
+		assert_eq!(cpu.state().cycle, 4);
+	}
+
+	#[test]
+	fn costs_and_trace() {
+		let costs = Costs { noop: 0, addx: 3 };
+		let mut cpu = Cpu::with_costs(program("noop\naddx 3\nnoop"), costs);
+		cpu.set_trace(true);
+		assert_eq!(cpu.by_ref().count(), 3);
+		assert_eq!(cpu.state().regs.x, 4);
+		assert_eq!(cpu.trace().len(), 3);
+		assert!(cpu.trace()[2].ends_with("addx 3 (3/3)"));
+	}
+
+	#[test]
+	fn parse_errors() {
+		assert!("addx".parse::<Instruction>().is_err());
+		assert!("noop 1".parse::<Instruction>().is_err());
+		assert!("mulx 2".parse::<Instruction>().is_err());
+	}
+}
@@ -0,0 +1,244 @@
+use std::fmt::Write;
+
+use crate::cpu::State;
+use crate::Result;
+
+/// Screen that draws one pixel per cycle, left to right and top to bottom,
+/// lighting it if the sprite centered on `X` covers the pixel's column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Crt {
+	w: usize,
+	h: usize,
+	sprite: usize,
+	pixels: Vec<bool>,
+}
+
+impl Crt {
+	pub fn new(w: usize, h: usize) -> Result<Self> {
+		if w == 0 || h == 0 {
+			return Err(format!("can't make a {w}x{h} screen").into());
+		}
+		Ok(Self {
+			w,
+			h,
+			sprite: 3,
+			pixels: vec![false; w * h],
+		})
+	}
+
+	pub fn with_sprite(self, sprite: usize) -> Self {
+		Self { sprite, ..self }
+	}
+
+	/// Columns covered by the sprite when `X` is at `x`. Even widths extend
+	/// further to the right.
+	pub fn covers(&self, x: isize, column: usize) -> bool {
+		// Saturates, a sprite that far off the screen covers none of it anyway.
+		let from = x.saturating_sub((self.sprite as isize - 1) / 2);
+		let to = from.saturating_add(self.sprite as isize);
+		(from..to).contains(&(column as isize))
+	}
+
+	/// Draws a frame from the states during each cycle, ignoring anything
+	/// past the last pixel.
+	pub fn draw<I>(&mut self, states: I)
+	where
+		I: IntoIterator<Item = State>,
+	{
+		for s in states.into_iter().take(self.w * self.h) {
+			let i = s.cycle - 1;
+			self.pixels[i] = self.covers(s.regs.x, i % self.w);
+		}
+	}
+
+	pub fn lit(&self, x: usize, y: usize) -> bool {
+		self.pixels[y * self.w + x]
+	}
+
+	/// Renders the screen as text, a line per row.
+	pub fn to_text(&self, on: char, off: char) -> String {
+		let mut text = String::with_capacity((self.w + 1) * self.h);
+		for row in self.pixels.chunks(self.w) {
+			text.extend(row.iter().map(|&p| if p { on } else { off }));
+			text.push('\n');
+		}
+		text
+	}
+
+	/// Renders the screen as a binary PBM, lit pixels being black.
+	pub fn to_pbm(&self) -> Vec<u8> {
+		let mut out = format!("P4\n{} {}\n", self.w, self.h).into_bytes();
+		for row in self.pixels.chunks(self.w) {
+			for bits in row.chunks(8) {
+				let b = bits
+					.iter()
+					.enumerate()
+					.fold(0u8, |b, (i, &p)| b | (p as u8) << (7 - i));
+				out.push(b);
+			}
+		}
+		out
+	}
+
+	/// Renders the screen as a grayscale PNG, each pixel being a `scale`-sized
+	/// square. Image data is stored uncompressed.
+	pub fn to_png(&self, scale: usize) -> Result<Vec<u8>> {
+		check_scale(scale)?;
+		let (w, h) = (self.w * scale, self.h * scale);
+
+		let mut raw = Vec::with_capacity((w + 1) * h);
+		for y in 0..h {
+			// No filtering for the scanline.
+			raw.push(0);
+			raw.extend((0..w).map(|x| if self.lit(x / scale, y / scale) { 0x00 } else { 0xff }));
+		}
+
+		let mut ihdr = Vec::new();
+		ihdr.extend((w as u32).to_be_bytes());
+		ihdr.extend((h as u32).to_be_bytes());
+		// 8-bit grayscale, default compression, filtering and no interlacing.
+		ihdr.extend([8, 0, 0, 0, 0]);
+
+		let mut out = b"\x89PNG\r\n\x1a\n".to_vec();
+		png_chunk(&mut out, b"IHDR", &ihdr);
+		png_chunk(&mut out, b"IDAT", &zlib_stored(&raw));
+		png_chunk(&mut out, b"IEND", &[]);
+		Ok(out)
+	}
+
+	/// Renders the screen as an SVG with a rectangle per lit pixel.
+	pub fn to_svg(&self, scale: usize) -> Result<String> {
+		check_scale(scale)?;
+		let mut svg = format!(
+			r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}">"#,
+			self.w * scale,
+			self.h * scale,
+		);
+		svg.push_str(r#"<rect width="100%" height="100%" fill="black"/>"#);
+		for y in 0..self.h {
+			for x in (0..self.w).filter(|&x| self.lit(x, y)) {
+				let _ = write!(
+					svg,
+					r#"<rect x="{}" y="{}" width="{scale}" height="{scale}" fill="lime"/>"#,
+					x * scale,
+					y * scale,
+				);
+			}
+		}
+		svg.push_str("</svg>\n");
+		Ok(svg)
+	}
+}
+
+fn check_scale(scale: usize) -> Result<()> {
+	if scale == 0 {
+		return Err("can't scale pixels by 0".into());
+	}
+	Ok(())
+}
+
+fn png_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+	out.extend((data.len() as u32).to_be_bytes());
+	let start = out.len();
+	out.extend(kind);
+	out.extend(data);
+	let crc = crc32(&out[start..]);
+	out.extend(crc.to_be_bytes());
+}
+
+fn crc32(data: &[u8]) -> u32 {
+	!data.iter().fold(!0u32, |crc, &b| {
+		(0..8).fold(crc ^ b as u32, |c, _| {
+			if c & 1 == 1 {
+				(c >> 1) ^ 0xedb88320
+			} else {
+				c >> 1
+			}
+		})
+	})
+}
+
+/// Wraps `data` into a zlib stream of uncompressed deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+	let mut out = vec![0x78, 0x01];
+	let mut blocks = data.chunks(0xffff).peekable();
+	if blocks.peek().is_none() {
+		out.extend([1, 0, 0, 0xff, 0xff]);
+	}
+	while let Some(block) = blocks.next() {
+		out.push(blocks.peek().is_none() as u8);
+		let len = block.len() as u16;
+		out.extend(len.to_le_bytes());
+		out.extend((!len).to_le_bytes());
+		out.extend(block);
+	}
+
+	let (a, b) = data.iter().fold((1u32, 0u32), |(a, b), &x| {
+		let a = (a + x as u32) % 65521;
+		(a, (b + a) % 65521)
+	});
+	out.extend((b << 16 | a).to_be_bytes());
+	out
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::cpu::Registers;
+
+	fn crt(xs: &[isize], w: usize, h: usize, sprite: usize) -> Crt {
+		let mut crt = Crt::new(w, h).unwrap().with_sprite(sprite);
+		crt.draw(xs.iter().enumerate().map(|(i, &x)| State {
+			cycle: i + 1,
+			pc: 0,
+			regs: Registers { x },
+		}));
+		crt
+	}
+
+	#[test]
+	fn sprite_width() {
+		let xs = [2; 6];
+		assert_eq!(crt(&xs, 6, 1, 3).to_text('#', '.'), ".###..\n");
+		assert_eq!(crt(&xs, 6, 1, 1).to_text('#', '.'), "..#...\n");
+		assert_eq!(crt(&xs, 6, 1, 4).to_text('#', '.'), ".####.\n");
+	}
+
+	#[test]
+	fn text() {
+		let xs = [0, 0, 0, 5, 5, 5, 9];
+		assert_eq!(crt(&xs, 3, 2, 3).to_text('#', '.'), "##.\n...\n");
+	}
+
+	#[test]
+	fn empty() {
+		assert!(Crt::new(0, 6).is_err());
+		assert!(Crt::new(40, 0).is_err());
+	}
+
+	#[test]
+	fn pbm() {
+		let xs = [0; 9];
+		let pbm = crt(&xs, 9, 1, 3).to_pbm();
+		assert_eq!(pbm, b"P4\n9 1\n\xc0\x00");
+	}
+
+	#[test]
+	fn png() {
+		let png = crt(&[1, 1], 2, 1, 1).to_png(1).unwrap();
+		assert!(png.starts_with(b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR"));
+		assert!(png.ends_with(b"IEND\xae\x42\x60\x82"));
+		// Scanline is the filter byte followed by a white and a black pixel.
+		assert!(png.windows(3).any(|w| w == [0x00, 0xff, 0x00]));
+		assert!(crt(&[1, 1], 2, 1, 1).to_png(0).is_err());
+	}
+
+	#[test]
+	fn svg() {
+		let svg = crt(&[1, 1], 2, 1, 1).to_svg(10).unwrap();
+		assert!(svg.contains(r#"width="20" height="10""#));
+		assert_eq!(svg.matches(r#"fill="lime""#).count(), 1);
+		assert!(svg.contains(r#"<rect x="10" y="0""#));
+		assert!(crt(&[1, 1], 2, 1, 1).to_svg(0).is_err());
+	}
+}
@@ -1,18 +1,57 @@
-#![feature(iter_repeat_n)]
+use std::env;
+use std::io::{self, Read, Write};
+use std::str;
 
-use std::io::{self, Read};
-use std::iter;
+mod asm;
+mod cpu;
+mod crt;
+mod ocr;
+mod synth;
+
+use asm::Program;
+use cpu::{Breakpoint, Costs, Cpu, Instruction, Stop};
+use crt::Crt;
 
 type Error = Box<dyn std::error::Error + Send + Sync>;
 type Result<T, E = Error> = std::result::Result<T, E>;
 
 fn main() -> Result<()> {
-	let mut input = String::new();
-	io::stdin().read_to_string(&mut input)?;
+	let mut input = Vec::new();
+	io::stdin().read_to_end(&mut input)?;
+
+	let args = env::args().skip(1).collect::<Vec<_>>();
+	let cmd = args.first().map(String::as_str);
+	// Bytecode is the only input that isn't text.
+	if cmd == Some("disasm") {
+		print!("{}", Program::from_bytes(&input)?);
+		return Ok(());
+	}
+
+	let input = str::from_utf8(&input)?;
+	match cmd {
+		Some("asm") => {
+			io::stdout().write_all(&asm::assemble(input)?.to_bytes())?;
+			return Ok(());
+		},
+		Some("render") => return render(input, &args[1..]),
+		Some("synth") => {
+			print!("{}", synth(input)?);
+			return Ok(());
+		},
+		// Any other arguments are flags for the debugger.
+		Some(_) => return debug(input, &args),
+		None => {},
+	}
 
-	println!("p1 = {}", part1(&input));
-	println!("p2:");
-	println!("{}", part2(&input));
+	println!("p1 = {}", part1(input)?);
+	let screen = part2(input)?;
+	match ocr::read(&screen) {
+		Ok(text) => println!("p2 = {text}"),
+		Err(e) => {
+			println!("p2 ({e}):");
+			println!("{screen}");
+		},
+	}
 
 	Ok(())
 }
@@ -20,75 +59,149 @@ fn main() -> Result<()> {
 const W: usize = 40;
 const H: usize = 6;
 
-fn part1(input: &str) -> isize {
-	signal(input)
-		.map(|(x, i)| (x, i + 1))
-		.map(|(x, i)| match i {
-			i if i % W == 20 => x * (i as isize),
-			_ => 0,
-		})
-		.sum()
+/// Runs the program with `--trace`, `--break <cycle|label>` and
+/// `--costs <noop>,<addx>` flags, reporting the machine state at each
+/// breakpoint.
+fn debug(input: &str, args: &[String]) -> Result<()> {
+	let mut costs = Costs::default();
+	let mut trace = false;
+	let mut breakpoints = Vec::new();
+	let mut args = args.iter();
+	while let Some(flag) = args.next() {
+		let mut value = || args.next().ok_or(format!("missing value for `{flag}`"));
+		match flag.as_str() {
+			"--trace" => trace = true,
+			"--break" => breakpoints.push(value()?),
+			"--costs" => {
+				let (noop, addx) = value()?.split_once(',').ok_or("expected `<noop>,<addx>`")?;
+				costs = Costs {
+					noop: noop.parse()?,
+					addx: addx.parse()?,
+				};
+			},
+			_ => return Err(format!("unknown flag `{flag}`").into()),
+		}
+	}
+
+	let program = asm::assemble(input)?;
+	let mut stops = Vec::new();
+	for at in breakpoints {
+		stops.push(match program.label(at) {
+			Some(pc) => Breakpoint::Pc(pc),
+			None => Breakpoint::Cycle(at.parse()?),
+		});
+	}
+
+	let mut cpu = Cpu::with_costs(program.code, costs);
+	cpu.set_trace(trace);
+	for b in stops {
+		cpu.add_breakpoint(b);
+	}
+
+	while let Stop::Breakpoint(b) = cpu.run(|_, _| {}) {
+		println!("{b:?}: {:?}", cpu.state());
+	}
+	for line in cpu.trace() {
+		println!("{line}");
+	}
+	overflow(&cpu)?;
+	println!("halted: {:?}", cpu.state());
+
+	Ok(())
 }
 
-fn part2(input: &str) -> String {
-	signal(input)
-		.take(W * H)
-		.map(|(s, c)| {
-			let x = (c % W) as isize;
-			if s - 1 <= x && x <= s + 1 {
-				'█'
-			} else {
-				'.'
-			}
-		})
-		.enumerate()
-		.flat_map(|(i, p)| {
-			if i % W == 0 {
-				Either::Left(['\n', p].into_iter())
-			} else {
-				Either::Right(iter::once(p))
-			}
-		})
-		.collect()
+/// Draws the program on a screen configured by `--size <w>x<h>`,
+/// `--sprite <width>`, `--scale <n>` and `--format <text|pbm|png|svg>` flags,
+/// writing the image to stdout.
+fn render(input: &str, args: &[String]) -> Result<()> {
+	let (mut w, mut h) = (W, H);
+	let mut sprite = 3;
+	let mut scale = 10;
+	let mut format = "text";
+	let mut args = args.iter();
+	while let Some(flag) = args.next() {
+		let mut value = || args.next().ok_or(format!("missing value for `{flag}`"));
+		match flag.as_str() {
+			"--size" => {
+				let (x, y) = value()?.split_once('x').ok_or("expected `<w>x<h>`")?;
+				(w, h) = (x.parse()?, y.parse()?);
+			},
+			"--sprite" => sprite = value()?.parse()?,
+			"--scale" => scale = value()?.parse()?,
+			"--format" => format = value()?,
+			_ => return Err(format!("unknown flag `{flag}`").into()),
+		}
+	}
+
+	let mut crt = Crt::new(w, h)?.with_sprite(sprite);
+	let mut cpu = Cpu::new(parse(input)?);
+	crt.draw(&mut cpu);
+	overflow(&cpu)?;
+	let image = match format {
+		"text" => crt.to_text('█', '.').into_bytes(),
+		"pbm" => crt.to_pbm(),
+		"png" => crt.to_png(scale)?,
+		"svg" => crt.to_svg(scale)?.into_bytes(),
+		_ => return Err(format!("unknown format `{format}`").into()),
+	};
+	io::stdout().write_all(&image)?;
+
+	Ok(())
 }
 
-fn signal(input: &str) -> impl Iterator<Item = (isize, usize)> + '_ {
-	input
+/// Finds a program drawing the picture, where lit pixels are `█` or `#`.
+fn synth(picture: &str) -> Result<Program> {
+	let rows = picture
 		.lines()
-		.map(|l| l.split_once(' ').and_then(|(_, v)| v.parse::<isize>().ok()))
-		.scan(1, |s, c| {
-			let x = *s;
-			if let Some(v) = c {
-				*s = x + v;
-				Some(iter::repeat_n(x, 2))
-			} else {
-				Some(iter::repeat_n(x, 1))
-			}
-		})
-		.flatten()
-		.zip(0..)
-}
+		.map(|l| l.trim_end())
+		.filter(|l| !l.is_empty())
+		.map(|l| l.chars().map(|c| c == '█' || c == '#').collect::<Vec<_>>())
+		.collect::<Vec<_>>();
+	let w = rows.first().map_or(0, Vec::len);
+	if w == 0 {
+		return Err("picture is empty".into());
+	}
+	if rows.iter().any(|r| r.len() != w) {
+		return Err("picture rows should have the same width".into());
+	}
 
-enum Either<L, R> {
-	Left(L),
-	Right(R),
+	let code = synth::synthesise(&rows.concat(), w)?;
+	Ok(Program {
+		code,
+		labels: Vec::new(),
+	})
 }
 
-impl<L, R> Iterator for Either<L, R>
-where
-	L: Iterator,
-	R: Iterator<Item = L::Item>,
-{
-	type Item = <L as Iterator>::Item;
+fn parse(input: &str) -> Result<Vec<Instruction>, asm::AsmError> {
+	Ok(asm::assemble(input)?.code)
+}
 
-	fn next(&mut self) -> Option<Self::Item> {
-		match self {
-			Either::Left(l) => l.next(),
-			Either::Right(r) => r.next(),
-		}
+/// Fails if `X` overflowed, which cut the program short.
+fn overflow(cpu: &Cpu) -> Result<()> {
+	match cpu.overflow() {
+		Some(pc) => Err(format!("x overflows at address {pc}").into()),
+		None => Ok(()),
 	}
 }
 
+fn part1(input: &str) -> Result<isize> {
+	let mut cpu = Cpu::new(parse(input)?);
+	let sum = cpu
+		.by_ref()
+		.filter(|s| s.cycle % W == 20)
+		.try_fold(0isize, |sum, s| sum.checked_add(s.regs.x.checked_mul(s.cycle as isize)?));
+	overflow(&cpu)?;
+	Ok(sum.ok_or("signal strength overflows")?)
+}
+
+fn part2(input: &str) -> Result<String> {
+	let mut crt = Crt::new(W, H)?;
+	let mut cpu = Cpu::new(parse(input)?);
+	crt.draw(&mut cpu);
+	overflow(&cpu)?;
+	Ok(crt.to_text('█', '.'))
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -242,7 +355,7 @@ noop"#;
 
 	#[test]
 	fn part1_example() {
-		assert_eq!(part1(INPUT), 13140);
+		assert_eq!(part1(INPUT).unwrap(), 13140);
 	}
 
 	#[test]
@@ -254,6 +367,23 @@ noop"#;
 ██████......██████......██████......████
 ███████.......███████.......███████....."#;
 
-		assert_eq!(part2(INPUT).trim(), answer);
+		assert_eq!(part2(INPUT).unwrap().trim(), answer);
+	}
+
+	#[test]
+	fn overflows() {
+		let err = part2("addx 9223372036854775806\naddx 1").unwrap_err();
+		assert_eq!(err.to_string(), "x overflows at address 1");
+		let input = format!("addx 9223372036854775806{}", "\nnoop".repeat(20));
+		assert_eq!(part1(&input).unwrap_err().to_string(), "signal strength overflows");
+	}
+
+	#[test]
+	fn synth_example() {
+		let screen = part2(INPUT).unwrap();
+		let program = synth(&screen).unwrap();
+		assert_eq!(part2(&program.to_string()).unwrap(), screen);
+		let addx = |code: &[Instruction]| code.iter().filter(|&&i| i != Instruction::Noop).count();
+		assert!(addx(&program.code) <= addx(&parse(INPUT).unwrap()));
 	}
 }
@@ -0,0 +1,180 @@
+use std::error;
+use std::fmt;
+
+/// Letters are drawn in a fixed-pitch font: each glyph occupies `w` columns
+/// followed by blank spacing up to `pitch` columns.
+struct Font {
+	w: usize,
+	h: usize,
+	pitch: usize,
+	glyphs: &'static [(char, &'static str)],
+}
+
+/// Standard font used by the CRT puzzles.
+const SMALL: Font = Font {
+	w: 4,
+	h: 6,
+	pitch: 5,
+	glyphs: &[
+		('A', ".##.#..##..######..##..#"),
+		('B', "###.#..####.#..##..####."),
+		('C', ".##.#..##...#...#..#.##."),
+		('E', "#####...###.#...#...####"),
+		('F', "#####...###.#...#...#..."),
+		('G', ".##.#..##...#.###..#.###"),
+		('H', "#..##..######..##..##..#"),
+		('I', ".###..#...#...#...#..###"),
+		('J', "..##...#...#...##..#.##."),
+		('K', "#..##.#.##..#.#.#.#.#..#"),
+		('L', "#...#...#...#...#...####"),
+		('O', ".##.#..##..##..##..#.##."),
+		('P', "###.#..##..####.#...#..."),
+		('R', "###.#..##..####.#.#.#..#"),
+		('S', ".####...#....##....####."),
+		('U', "#..##..##..##..##..#.##."),
+		('Z', "####...#..#..#..#...####"),
+	],
+};
+
+/// Larger font seen in the star-message puzzles.
+const LARGE: Font = Font {
+	w: 6,
+	h: 10,
+	pitch: 8,
+	glyphs: &[
+		('A', "..##...#..#.#....##....##....########....##....##....##....#"),
+		('B', "#####.#....##....##....######.#....##....##....##....######."),
+		('C', ".####.#....##.....#.....#.....#.....#.....#.....#....#.####."),
+		('E', "#######.....#.....#.....#####.#.....#.....#.....#.....######"),
+		('F', "#######.....#.....#.....#####.#.....#.....#.....#.....#....."),
+		('G', ".####.#....##.....#.....#.....#..####....##....##...##.###.#"),
+		('H', "#....##....##....##....########....##....##....##....##....#"),
+		('J', "...###....#.....#.....#.....#.....#.....#.#...#.#...#..###.."),
+		('K', "#....##...#.#..#..#.#...##....##....#.#...#..#..#...#.#....#"),
+		('L', "#.....#.....#.....#.....#.....#.....#.....#.....#.....######"),
+		('N', "#....###...###...##.#..##.#..##..#.##..#.##...###...###....#"),
+		('P', "#####.#....##....##....######.#.....#.....#.....#.....#....."),
+		('R', "#####.#....##....##....######.#..#..#...#.#...#.#....##....#"),
+		('X', "#....##....#.#..#..#..#...##....##...#..#..#..#.#....##....#"),
+		('Z', "######.....#.....#....#....#....#....#....#.....#.....######"),
+	],
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OcrError {
+	/// No font is as tall as the screen.
+	UnknownHeight(usize),
+	/// Columns at which glyphs couldn't be matched against the font.
+	Unrecognised(Vec<usize>),
+}
+
+impl fmt::Display for OcrError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			OcrError::UnknownHeight(h) => write!(f, "no font is {h} pixels high"),
+			OcrError::Unrecognised(columns) => {
+				write!(f, "unrecognised glyphs at columns {columns:?}")
+			},
+		}
+	}
+}
+
+impl error::Error for OcrError {}
+
+/// Reads letters off a rendered screen, where lit pixels are `█` or `#` and
+/// everything else is dark. The font is picked by the screen height.
+pub fn read(screen: &str) -> Result<String, OcrError> {
+	let rows = screen
+		.lines()
+		.map(|l| l.trim_end())
+		.filter(|l| !l.is_empty())
+		.map(|l| l.chars().map(|c| c == '█' || c == '#').collect::<Vec<_>>())
+		.collect::<Vec<_>>();
+
+	let font = match rows.len() {
+		6 => &SMALL,
+		10 => &LARGE,
+		h => return Err(OcrError::UnknownHeight(h)),
+	};
+	let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+	let lit = |x: usize, y: usize| rows[y].get(x).copied().unwrap_or(false);
+
+	let mut text = String::new();
+	let mut columns = Vec::new();
+	for x in (0..width).step_by(font.pitch) {
+		let glyph = (0..font.h)
+			.flat_map(|y| (x..x + font.w).map(move |x| (x, y)))
+			.map(|(x, y)| if lit(x, y) { '#' } else { '.' })
+			.collect::<String>();
+		if !glyph.contains('#') {
+			continue;
+		}
+		match font.glyphs.iter().find(|(_, g)| *g == glyph) {
+			Some(&(c, _)) => text.push(c),
+			None => columns.push(x),
+		}
+	}
+
+	if columns.is_empty() {
+		Ok(text)
+	} else {
+		Err(OcrError::Unrecognised(columns))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn draw(font: &Font, text: &str) -> String {
+		let glyphs = text
+			.chars()
+			.map(|c| font.glyphs.iter().find(|(g, _)| *g == c).unwrap().1.as_bytes())
+			.collect::<Vec<_>>();
+		let mut screen = String::new();
+		for y in 0..font.h {
+			for g in &glyphs {
+				for &b in &g[y * font.w..(y + 1) * font.w] {
+					screen.push(b as char);
+				}
+				for _ in font.w..font.pitch {
+					screen.push('.');
+				}
+			}
+			screen.push('\n');
+		}
+		screen
+	}
+
+	#[test]
+	fn fonts_are_well_formed() {
+		for font in [&SMALL, &LARGE] {
+			for (c, g) in font.glyphs {
+				assert_eq!(g.len(), font.w * font.h, "glyph {c}");
+			}
+		}
+	}
+
+	#[test]
+	fn small_font() {
+		let text = "ZFBFHGUP";
+		assert_eq!(read(&draw(&SMALL, text)), Ok(text.to_string()));
+	}
+
+	#[test]
+	fn large_font() {
+		let text = "HZKJXNRA";
+		assert_eq!(read(&draw(&LARGE, text)), Ok(text.to_string()));
+	}
+
+	#[test]
+	fn unrecognised() {
+		let mut screen = draw(&SMALL, "ABC");
+		// Lights the top-right pixel of the `B`, which is dark otherwise.
+		screen.replace_range(8..9, "#");
+		assert_eq!(read(&screen), Err(OcrError::Unrecognised(vec![5])));
+
+		let short = screen.lines().skip(1).collect::<Vec<_>>().join("\n");
+		assert_eq!(read(&short), Err(OcrError::UnknownHeight(5)));
+	}
+}
@@ -0,0 +1,194 @@
+use std::error;
+use std::fmt;
+
+use crate::cpu::Instruction;
+use crate::crt::Crt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SynthError {
+	/// Target with no pixels, or that isn't made of whole rows.
+	InvalidSize { len: usize, w: usize },
+	/// Target that can't be drawn exactly, with the pixels that differ in the
+	/// closest drawing and the program for it.
+	Unreachable {
+		pixels: Vec<(usize, usize)>,
+		closest: Vec<Instruction>,
+	},
+}
+
+impl fmt::Display for SynthError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			SynthError::InvalidSize { len, w } => {
+				write!(f, "can't draw {len} pixels in rows of {w}")
+			},
+			SynthError::Unreachable { pixels, .. } => {
+				write!(f, "unreachable pixels at {pixels:?}")
+			},
+		}
+	}
+}
+
+impl error::Error for SynthError {}
+
+/// Finds a program drawing `target`, a row-major bitmap `w` pixels wide, on
+/// the default CRT.
+///
+/// `X` only changes once an `addx` completes, so the value drawn during each
+/// cycle stays the same for runs of at least two cycles, apart from the last
+/// one. Runs are picked to minimise the number of wrong pixels and then the
+/// number of `addx` instructions.
+pub fn synthesise(target: &[bool], w: usize) -> Result<Vec<Instruction>, SynthError> {
+	let invalid = || SynthError::InvalidSize { len: target.len(), w };
+	if w == 0 || !target.len().is_multiple_of(w) {
+		return Err(invalid());
+	}
+	let crt = Crt::new(w, target.len() / w).map_err(|_| invalid())?;
+	// Anything outside of these covers no column at all.
+	let (lo, hi) = (-2, w as isize + 1);
+	let xs = (hi - lo + 1) as usize;
+	let x = |i: usize| lo + i as isize;
+	let wrong = |c: usize, i: usize| (crt.covers(x(i), c % w) != target[c]) as u32;
+
+	// Cost of drawing up to a cycle with some `X`, whose run is either a
+	// single cycle or longer, and where we came from.
+	type Cell = ((u32, u32), usize, usize);
+	let mut best: Vec<[Vec<Cell>; 2]> = Vec::with_capacity(target.len());
+	let none = ((u32::MAX, u32::MAX), 0, 0);
+
+	for c in 0..target.len() {
+		let mut cur = [vec![none; xs], vec![none; xs]];
+		if c == 0 {
+			let start = (1 - lo) as usize;
+			cur[0][start] = ((wrong(c, start), 0), start, 0);
+			best.push(cur);
+			continue;
+		}
+
+		let prev = &best[c - 1];
+		for i in 0..xs {
+			// Keeps the same value for one more cycle.
+			for (r, p) in prev.iter().enumerate() {
+				let ((e, s), _, _) = p[i];
+				if e != u32::MAX && (e, s) < cur[1][i].0 {
+					cur[1][i] = ((e, s), i, r);
+				}
+			}
+			// Or finishes an `addx` which moves `X` from a long enough run.
+			for (j, &((e, s), _, _)) in prev[1].iter().enumerate() {
+				if j != i && e != u32::MAX && (e, s + 1) < cur[0][i].0 {
+					cur[0][i] = ((e, s + 1), j, 1);
+				}
+			}
+		}
+		for r in &mut cur {
+			for (i, ((e, _), _, _)) in r.iter_mut().enumerate() {
+				if *e != u32::MAX {
+					*e += wrong(c, i);
+				}
+			}
+		}
+		best.push(cur);
+	}
+
+	// Walks back from the cheapest final state to get `X` for every cycle.
+	let last = &best[target.len() - 1];
+	let (mut i, mut r) = (0..xs)
+		.flat_map(|i| [(i, 0), (i, 1)])
+		.min_by_key(|&(i, r)| last[r][i].0)
+		.unwrap_or_default();
+	let mut path = vec![0; target.len()];
+	for c in (0..target.len()).rev() {
+		path[c] = i;
+		let (_, pi, pr) = best[c][r][i];
+		(i, r) = (pi, pr);
+	}
+
+	let mut program = Vec::new();
+	let mut c = 0;
+	while c < path.len() {
+		let run = path[c..].iter().take_while(|&&i| i == path[c]).count();
+		match path.get(c + run) {
+			Some(&next) => {
+				program.extend((0..run - 2).map(|_| Instruction::Noop));
+				program.push(Instruction::Addx(x(next) - x(path[c])));
+			},
+			None => program.extend((0..run).map(|_| Instruction::Noop)),
+		}
+		c += run;
+	}
+
+	let pixels = (0..target.len())
+		.filter(|&c| wrong(c, path[c]) != 0)
+		.map(|c| (c % w, c / w))
+		.collect::<Vec<_>>();
+	if pixels.is_empty() {
+		Ok(program)
+	} else {
+		Err(SynthError::Unreachable {
+			pixels,
+			closest: program,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::cpu::Cpu;
+
+	fn draw(program: Vec<Instruction>, w: usize, h: usize) -> Vec<bool> {
+		let mut crt = Crt::new(w, h).unwrap();
+		crt.draw(Cpu::new(program));
+		(0..w * h).map(|i| crt.lit(i % w, i / w)).collect()
+	}
+
+	#[test]
+	fn round_trips_programs() {
+		let programs: [Vec<Instruction>; 4] = [
+			vec![Instruction::Noop; 240],
+			// Sprite bouncing back and forth, landing on every other column.
+			(0..120).map(|i| Instruction::Addx(if i % 2 == 0 { 5 } else { -5 })).collect(),
+			// Drifting off both edges of the screen.
+			(-10..=10).cycle().take(150).map(Instruction::Addx).collect(),
+			(0..200)
+				.map(|i| match i % 3 {
+					0 => Instruction::Noop,
+					_ => Instruction::Addx(i % 7 - 3),
+				})
+				.collect(),
+		];
+		for program in programs {
+			let target = draw(program, 40, 6);
+			let synthesised = synthesise(&target, 40).unwrap();
+			assert_eq!(draw(synthesised, 40, 6), target);
+		}
+	}
+
+	#[test]
+	fn reports_unreachable() {
+		// `X` is 1 during the first two cycles, so the second pixel is lit.
+		let target = "#.#.......".bytes().map(|b| b == b'#').collect::<Vec<_>>();
+		let Err(SynthError::Unreachable { pixels, closest }) = synthesise(&target, 10) else {
+			panic!("the second pixel should be unreachable");
+		};
+		assert_eq!(pixels, [(1, 0)]);
+		let drawn = draw(closest, 10, 1);
+		let wrong = (0..10).filter(|&i| drawn[i] != target[i]).collect::<Vec<_>>();
+		assert_eq!(wrong, [1]);
+
+		let Err(SynthError::Unreachable { pixels, closest }) = synthesise(&[false; 40 * 6], 40)
+		else {
+			panic!("a dark screen should be unreachable");
+		};
+		assert_eq!(pixels, [(0, 0), (1, 0)]);
+		assert_eq!(closest.iter().filter(|i| **i != Instruction::Noop).count(), 1);
+	}
+
+	#[test]
+	fn invalid_size() {
+		assert_eq!(synthesise(&[], 40), Err(SynthError::InvalidSize { len: 0, w: 40 }));
+		assert!(synthesise(&[true; 4], 0).is_err());
+		assert!(synthesise(&[true; 5], 2).is_err());
+	}
+}
@@ -1,7 +1,10 @@
 #![feature(iter_array_chunks)]
 
+use std::env;
+use std::fmt;
 use std::io::{self, Read};
 use std::iter;
+use std::str::FromStr;
 
 type Error = Box<dyn std::error::Error + Send + Sync>;
 type Result<T, E = Error> = std::result::Result<T, E>;
@@ -10,20 +13,184 @@ fn main() -> Result<()> {
 	let mut input = String::new();
 	io::stdin().read_to_string(&mut input)?;
 
-	println!("p1 = {}", part1(&input));
-	println!("p2 = {}", part2(&input));
+	if let Some(config) = MonkeyConfig::from_args(env::args().skip(1))? {
+		let counts = play(&mut parse(&input), &config)?;
+		println!("{}", config.score.apply(counts)?);
+		return Ok(());
+	}
+
+	println!("p1 = {}", part1(&input)?);
+	println!("p2 = {}", part2(&input)?);
 
 	Ok(())
 }
 
-fn part1(input: &str) -> u64 {
-	solve(&mut parse(input), 20, |x| x / 3)
+fn part1(input: &str) -> Result<u64> {
+	let counts = play(&mut parse(input), &MonkeyConfig::part1())?;
+	product(&counts, 2)
+}
+
+fn part2(input: &str) -> Result<u64> {
+	let counts = play(&mut parse(input), &MonkeyConfig::part2())?;
+	product(&counts, 2)
+}
+
+/// Describes a variant of the puzzle: how worry levels are kept in check,
+/// how long the monkeys play and how their business is scored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct MonkeyConfig {
+	relief: Relief,
+	rounds: usize,
+	score: Score,
+}
+
+impl MonkeyConfig {
+	fn part1() -> Self {
+		Self {
+			relief: Relief::Div(3),
+			rounds: 20,
+			score: Score::Product(2),
+		}
+	}
+
+	fn part2() -> Self {
+		Self {
+			relief: Relief::Mod,
+			rounds: 10000,
+			score: Score::Product(2),
+		}
+	}
+
+	/// Builds a config from `--relief`, `--rounds` and `--score` flags,
+	/// starting from part 1 settings. Returns `None` if no flags are given.
+	fn from_args<I>(args: I) -> Result<Option<Self>>
+	where
+		I: IntoIterator<Item = String>,
+	{
+		let mut args = args.into_iter().peekable();
+		if args.peek().is_none() {
+			return Ok(None);
+		}
+
+		let mut config = Self::part1();
+		while let Some(flag) = args.next() {
+			let value = args
+				.next()
+				.ok_or_else(|| format!("missing value for `{flag}`"))?;
+			match flag.as_str() {
+				"--relief" => config.relief = value.parse()?,
+				"--rounds" => config.rounds = value.parse()?,
+				"--score" => config.score = value.parse()?,
+				_ => return Err(format!("unknown flag `{flag}`").into()),
+			}
+		}
+
+		Ok(Some(config))
+	}
+}
+
+/// How the worry level is reduced after each inspection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Relief {
+	/// Divides the worry level, as in part 1.
+	Div(u64),
+	/// Keeps the worry level modulo the product of all tests, as in part 2.
+	Mod,
+	/// No relief at all.
+	None,
+}
+
+impl Relief {
+	fn apply(self, w: u64, base: u64) -> u64 {
+		match self {
+			Relief::Div(d) => w / d,
+			Relief::Mod => w % base,
+			Relief::None => w,
+		}
+	}
+}
+
+impl FromStr for Relief {
+	type Err = Error;
+
+	fn from_str(s: &str) -> Result<Self> {
+		match s.split_once('=') {
+			Some(("div", d)) => match d.parse()? {
+				0 => Err("relief divisor should be positive".into()),
+				d => Ok(Relief::Div(d)),
+			},
+			None if s == "mod" => Ok(Relief::Mod),
+			None if s == "none" => Ok(Relief::None),
+			_ => Err(format!("unknown relief `{s}`, expected `div=N`, `mod` or `none`").into()),
+		}
+	}
 }
 
-fn part2(input: &str) -> u64 {
-	let mut monkeys = parse(input);
-	let base = monkeys.iter().fold(1, |b, m| b * m.test as u64);
-	solve(&mut monkeys, 10000, |x| x % base)
+/// How the monkey business level is computed from inspection counts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Score {
+	/// Product of the `k` most active monkeys' counts.
+	Product(usize),
+	/// Sum of the `k` most active monkeys' counts.
+	Sum(usize),
+	/// All counts, most active monkey first.
+	Ranking,
+}
+
+impl FromStr for Score {
+	type Err = Error;
+
+	fn from_str(s: &str) -> Result<Self> {
+		match s.split_once('=') {
+			Some(("product", k)) => Ok(Score::Product(k.parse()?)),
+			Some(("sum", k)) => Ok(Score::Sum(k.parse()?)),
+			None if s == "ranking" => Ok(Score::Ranking),
+			_ => Err(format!("unknown score `{s}`, expected `product=K`, `sum=K` or `ranking`").into()),
+		}
+	}
+}
+
+impl Score {
+	/// Scores inspection counts, most active monkey first.
+	fn apply(self, counts: Vec<u64>) -> Result<Business> {
+		match self {
+			Score::Product(k) => Ok(Business::Total(product(&counts, k)?)),
+			Score::Sum(k) => {
+				let sum = counts.iter().take(k).try_fold(0u64, |s, &c| s.checked_add(c));
+				Ok(Business::Total(sum.ok_or("monkey business overflows")?))
+			},
+			Score::Ranking => Ok(Business::Ranking(counts)),
+		}
+	}
+}
+
+/// Product of the first `k` inspection counts.
+fn product(counts: &[u64], k: usize) -> Result<u64> {
+	let product = counts.iter().take(k).try_fold(1u64, |p, &c| p.checked_mul(c));
+	Ok(product.ok_or("monkey business overflows")?)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Business {
+	Total(u64),
+	Ranking(Vec<u64>),
+}
+
+impl fmt::Display for Business {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Business::Total(t) => write!(f, "{t}"),
+			Business::Ranking(r) => {
+				for (i, t) in r.iter().enumerate() {
+					if i > 0 {
+						write!(f, ", ")?;
+					}
+					write!(f, "{t}")?;
+				}
+				Ok(())
+			},
+		}
+	}
 }
 
 fn parse(input: &str) -> Vec<Monkey> {
@@ -49,11 +216,15 @@ fn parse(input: &str) -> Vec<Monkey> {
 		.collect()
 }
 
-fn solve<F>(monkeys: &mut [Monkey], rounds: usize, f: F) -> u64
-where
-	F: Fn(u64) -> u64,
-{
-	for _ in 0..rounds {
+/// Plays the rounds and returns how many items each monkey inspected, most
+/// active first. Fails if a worry level doesn't fit in a `u64`, which
+/// happens quickly without relief.
+fn play(monkeys: &mut [Monkey], config: &MonkeyConfig) -> Result<Vec<u64>> {
+	let base = monkeys
+		.iter()
+		.try_fold(1u64, |b, m| b.checked_mul(m.test as u64))
+		.ok_or("product of the tests overflows")?;
+	for round in 1..=config.rounds {
 		for m in 0..monkeys.len() {
 			let Monkey {
 				op,
@@ -64,12 +235,14 @@ where
 			} = monkeys[m];
 			for i in 0..monkeys[m].items.len() {
 				let w = monkeys[m].items[i];
-				let w = f(match op.0 {
-					b'+' => w + op.1.unwrap_or(w),
-					b'*' => w * op.1.unwrap_or(w),
-					_ => w,
-				});
-				let n = if w % test as u64 == 0 { pass } else { fail };
+				let w = match op.0 {
+					b'+' => w.checked_add(op.1.unwrap_or(w)),
+					b'*' => w.checked_mul(op.1.unwrap_or(w)),
+					_ => Some(w),
+				}
+				.ok_or_else(|| format!("worry level overflows in round {round}, monkey {m}"))?;
+				let w = config.relief.apply(w, base);
+				let n = if w.is_multiple_of(test as u64) { pass } else { fail };
 				monkeys[n].items.push(w);
 			}
 			monkeys[m].times += monkeys[m].items.len() as u64;
@@ -77,8 +250,9 @@ where
 		}
 	}
 
-	monkeys.sort_by(|a, b| b.times.cmp(&a.times));
-	monkeys.into_iter().map(|m| m.times).take(2).product()
+	let mut times = monkeys.iter().map(|m| m.times).collect::<Vec<_>>();
+	times.sort_unstable_by(|a, b| b.cmp(a));
+	Ok(times)
 }
 
 struct Monkey {
@@ -160,11 +334,61 @@ Monkey 3:
 
 	#[test]
 	fn part1_example() {
-		assert_eq!(part1(INPUT), 10605);
+		assert_eq!(part1(INPUT).unwrap(), 10605);
 	}
 
 	#[test]
 	fn part2_example() {
-		assert_eq!(part2(INPUT), 2713310158);
+		assert_eq!(part2(INPUT).unwrap(), 2713310158);
+	}
+
+	#[test]
+	fn custom_config() {
+		let counts = play(&mut parse(INPUT), &MonkeyConfig::part1()).unwrap();
+		assert_eq!(
+			Score::Ranking.apply(counts).unwrap(),
+			Business::Ranking(vec![105, 101, 95, 7])
+		);
+
+		let config = MonkeyConfig {
+			rounds: 1,
+			..MonkeyConfig::part2()
+		};
+		let counts = play(&mut parse(INPUT), &config).unwrap();
+		assert_eq!(Score::Sum(4).apply(counts).unwrap(), Business::Total(2 + 4 + 3 + 6));
+	}
+
+	#[test]
+	fn overflow() {
+		let config = MonkeyConfig {
+			relief: Relief::None,
+			..MonkeyConfig::part1()
+		};
+		let err = play(&mut parse(INPUT), &config).unwrap_err();
+		assert!(err.to_string().starts_with("worry level overflows in round"));
+
+		let counts = vec![u64::MAX, 2, 1];
+		assert!(Score::Product(2).apply(counts.clone()).is_err());
+		assert!(Score::Sum(2).apply(counts.clone()).is_err());
+		assert_eq!(Score::Sum(1).apply(counts).unwrap(), Business::Total(u64::MAX));
+	}
+
+	#[test]
+	fn config_from_args() {
+		let args = ["--rounds", "1000", "--relief", "mod", "--score", "ranking"];
+		let config = MonkeyConfig::from_args(args.map(String::from)).unwrap();
+		assert_eq!(
+			config,
+			Some(MonkeyConfig {
+				relief: Relief::Mod,
+				rounds: 1000,
+				score: Score::Ranking,
+			})
+		);
+
+		assert_eq!(MonkeyConfig::from_args(Vec::new()).unwrap(), None);
+		assert!(MonkeyConfig::from_args(["--relief".to_string()]).is_err());
+		assert!("div=0".parse::<Relief>().is_err());
+		assert!("top=2".parse::<Score>().is_err());
 	}
 }
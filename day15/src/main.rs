@@ -1,5 +1,6 @@
 use std::io::{self, Read};
-use std::collections::HashSet;
+
+use day04::interval::{Interval, IntervalSet};
 
 type Error = Box<dyn std::error::Error + Send + Sync>;
 type Result<T, E = Error> = std::result::Result<T, E>;
@@ -16,28 +17,21 @@ fn main() -> Result<()> {
 }
 
 fn part1(data: &[(Point, Point)], row: i32) -> i64 {
-	let mut uniq = HashSet::new();
-
-	for (sensor, beacon) in data {
-		let d = distance(*sensor, *beacon);
-
-		for dx in 0.. {
-			if dx + (sensor.1 - row).abs() > d {
-				break;
-			} else {
-				uniq.insert(sensor.0 + dx);
-				uniq.insert(sensor.0 - dx);
-			}
-		}
-	}
-
-	for (_, beacon) in data {
-		if beacon.1 == row {
-			uniq.remove(&beacon.0);
-		}
-	}
+	let covered = data
+		.iter()
+		.filter_map(|&(sensor, beacon)| {
+			let reach = distance(sensor, beacon) - (sensor.1 - row).abs();
+			let x = sensor.0 as i64;
+			(reach >= 0).then(|| Interval::new(x - reach as i64, x + reach as i64))
+		})
+		.collect::<IntervalSet>();
+	let beacons = data
+		.iter()
+		.filter(|(_, beacon)| beacon.1 == row)
+		.map(|(_, beacon)| Interval::new(beacon.0 as i64, beacon.0 as i64))
+		.collect::<IntervalSet>();
 
-	uniq.len() as i64
+	covered.difference(&beacons).len() as i64
 }
 
 fn part2<const N: i64>(data: &[(Point, Point)]) -> i64 {